@@ -1,12 +1,33 @@
 use gpui::{Action, KeyBinding, actions};
 
-use crate::config::{self, CONFIG};
+use crate::{
+    config::{self, CONFIG},
+    histogram::Channel,
+};
+
+/// Runs the [config::CustomCommand] at the given index of [config::Config::commands].
+///
+/// Unlike the other actions, this one is not configurable via [config::Config::keybindings],
+/// instead the key is configured together with the command.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(no_json)]
+pub struct RunCommand {
+    pub index: usize,
+}
+
+/// Shows or hides the channel in the histogram, dispatched by clicking on the channel's label.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(no_json)]
+pub struct ToggleHistogramChannel {
+    pub channel: Channel,
+}
 
 /// Given the action names and their default key bindings,
-/// this macro does three things:
+/// this macro does four things:
 /// - Invoke [gpui::actions!] for each of the action names
 /// - Create a method `default_key_bindings` containing a list of all default key bindings
 /// - Create a method `convert_to_gpui_keybindings` which converts the [config::KeyBinding]s to [gpui::KeyBinding].
+/// - Create a method `build_action` which creates an action from its (case-insensitive) name.
 ///
 /// This is necessary to avoid duplicated code here and because actions created by the
 /// [gpui::actions!] macro don't implement [Clone], making them hard to handle.
@@ -25,6 +46,17 @@ macro_rules! build_actions_with_key_bindings {
                 $(create_key_bindings(&$action_name, key_bindings),)*
             ].iter().flatten().cloned().collect()
         }
+
+        /// Create the action with the given name, compared case-insensitively (like in [config::KeyBinding::action]).
+        pub fn build_action(name: &str) -> Option<Box<dyn Action>> {
+            $(
+                if $action_name.name().eq_ignore_ascii_case(name) {
+                    return Some($action_name.boxed_clone());
+                }
+            )*
+
+            None
+        }
     };
 }
 
@@ -51,17 +83,107 @@ build_actions_with_key_bindings!(
     (MoveLeft, "left"),
     (MoveRight, "right"),
     (OpenFiles, "o"),
-    (ToggleImageInfo, "i")
+    (ToggleImageInfo, "i"),
+    (ToggleMark, "m"),
+    (Pick, "enter"),
+    (TogglePlayback, "space"),
+    (NextFrame, "."),
+    (PreviousFrame, ","),
+    (IncreasePlaybackSpeed, "]"),
+    (DecreasePlaybackSpeed, "["),
+    (ToggleAnimationLoop, "shift-l"),
+    (NextPage, "pagedown"),
+    (PreviousPage, "pageup"),
+    (ToggleSpread, "d"),
+    (ToggleReadingDirection, "shift-d"),
+    (ToggleContinuousScroll, "c"),
+    (CopyImage, "ctrl-c"),
+    (CopyPath, "ctrl-shift-c"),
+    (PasteImage, "ctrl-v"),
+    (SaveImage, "ctrl-s"),
+    (ToggleColorManagement, "shift-c"),
+    (ToggleHistogram, "shift-h"),
+    (ToggleHistogramScale, "alt-h"),
+    (TogglePixelInspector, "p"),
+    (CopyColor, "y"),
+    (CycleBackdrop, "b"),
+    (ToggleCompare, "v"),
+    (CycleCompareMode, "shift-v"),
+    (IncreaseBlendOpacity, "alt-up"),
+    (DecreaseBlendOpacity, "alt-down"),
+    (ToggleCrop, "x"),
+    (CycleCropAspectRatio, "shift-x"),
+    (ToggleCropPreview, "alt-x"),
+    (SaveAs, "ctrl-shift-s"),
+    (IncreaseExposure, "e"),
+    (DecreaseExposure, "shift-e"),
+    (IncreaseContrast, "k"),
+    (DecreaseContrast, "shift-k"),
+    (IncreaseGamma, "u"),
+    (DecreaseGamma, "shift-u"),
+    (IncreaseSaturation, "s"),
+    (DecreaseSaturation, "shift-s"),
+    (ResetAdjustments, "r"),
+    (ShowRedChannel, "alt-r"),
+    (ShowGreenChannel, "alt-g"),
+    (ShowBlueChannel, "alt-b"),
+    (ShowAlphaChannel, "alt-a"),
+    (ShowAlphaMask, "alt-m"),
+    (ShowPremultipliedAlpha, "alt-p"),
+    (CycleSampling, "n"),
+    (TogglePixelGrid, "shift-n")
 );
 
 /// Build a vec of [gpui::KeyBinding] from the [config::KeyBinding]s configured in [crate::config::CONFIG]
-/// (or the default ones if none were provided).
+/// (or the default ones if none were provided) and the keys of the [config::CustomCommand]s.
 ///
 /// These should later be registered to the app using using [gpui::App::bind_keys].
 pub fn build_key_bindings_from_config() -> Vec<KeyBinding> {
-    let configured_key_bindings = &CONFIG.get().unwrap().keybindings;
+    let config = CONFIG.get().unwrap();
+
+    let mut key_bindings = convert_to_gpui_keybindings(&config.keybindings);
+    key_bindings.extend(
+        config
+            .commands
+            .iter()
+            .enumerate()
+            .map(|(index, command)| KeyBinding::new(&command.key, RunCommand { index }, None)),
+    );
+
+    key_bindings
+}
+
+/// Create the action with the given name like [build_action], additionally supporting the
+/// actions with an argument, written as `<name>:<argument>`:
+/// - `runcommand:<index>` runs the [config::CustomCommand] at the index, starting at 0
+/// - `togglehistogramchannel:<channel>` with the channel `red`, `green`, `blue` or `luminance`
+pub fn build_action_with_argument(name: &str) -> Option<Box<dyn Action>> {
+    let Some((name, argument)) = name.split_once(':') else {
+        return build_action(name);
+    };
+
+    if name.eq_ignore_ascii_case("runcommand") {
+        let index = argument.parse().ok()?;
+        if index >= CONFIG.get().unwrap().commands.len() {
+            return None;
+        }
+
+        return Some(Box::new(RunCommand { index }));
+    }
+
+    if name.eq_ignore_ascii_case("togglehistogramchannel") {
+        let channel = match argument.to_lowercase().as_str() {
+            "red" => Channel::Red,
+            "green" => Channel::Green,
+            "blue" => Channel::Blue,
+            "luminance" => Channel::Luminance,
+            _ => return None,
+        };
+
+        return Some(Box::new(ToggleHistogramChannel { channel }));
+    }
 
-    convert_to_gpui_keybindings(configured_key_bindings)
+    None
 }
 
 /// Takes a [gpui::Action] and builds [gpui::KeyBinding]s for all of the provided
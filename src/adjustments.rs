@@ -0,0 +1,117 @@
+//! Adjustments of exposure, contrast, gamma and saturation, which only affect how images
+//! are shown, e.g. for checking dark renders.
+
+use image::{Rgba, RgbaImage};
+
+use crate::config::CONFIG;
+
+/// The adjustments that can be changed step by step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Adjustment {
+    Exposure,
+    Contrast,
+    Gamma,
+    Saturation,
+}
+
+/// The adjustments applied to the shown image, the default changing nothing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adjustments {
+    /// Exposure compensation in stops (EV), applied to linear light.
+    pub exposure: f32,
+    /// Factor the distance of each value from mid-gray is multiplied with.
+    pub contrast: f32,
+    pub gamma: f32,
+    /// Factor the distance of each color from its luma is multiplied with.
+    pub saturation: f32,
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+impl Adjustments {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Change the adjustment by the configured step, in the direction of `sign`.
+    pub fn step(&mut self, adjustment: Adjustment, sign: f32) {
+        let config = CONFIG.get().unwrap();
+
+        match adjustment {
+            Adjustment::Exposure => self.exposure += sign * config.exposure_step,
+            Adjustment::Contrast => {
+                self.contrast = (self.contrast + sign * config.contrast_step).max(0.0);
+            }
+            Adjustment::Gamma => {
+                self.gamma = (self.gamma + sign * config.gamma_step).max(config.gamma_step);
+            }
+            Adjustment::Saturation => {
+                self.saturation = (self.saturation + sign * config.saturation_step).max(0.0);
+            }
+        }
+
+        // avoid showing e.g. "-0.00" after stepping back and forth
+        for value in [
+            &mut self.exposure,
+            &mut self.contrast,
+            &mut self.gamma,
+            &mut self.saturation,
+        ] {
+            *value = (*value * 1000.0).round() / 1000.0;
+        }
+    }
+
+    /// The adjusted copy of `frame`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn apply(&self, frame: &RgbaImage) -> RgbaImage {
+        // exposure, contrast and gamma affect each channel independently
+        let lookup_table: [u8; 256] = std::array::from_fn(|value| {
+            let value = f32::from(u8::try_from(value).unwrap()) / 255.0;
+            let linear = srgb_to_linear(value) * self.exposure.exp2();
+            let value = (linear_to_srgb(linear) - 0.5) * self.contrast + 0.5;
+            let value = value.clamp(0.0, 1.0).powf(1.0 / self.gamma);
+
+            (value * 255.0).round() as u8
+        });
+
+        let mut adjusted = frame.clone();
+        for Rgba([r, g, b, _]) in adjusted.pixels_mut() {
+            let [red, green, blue] =
+                [*r, *g, *b].map(|value| f32::from(lookup_table[usize::from(value)]));
+            let luma = 0.299 * red + 0.587 * green + 0.114 * blue;
+            let saturate =
+                |value: f32| (luma + (value - luma) * self.saturation).clamp(0.0, 255.0) as u8;
+
+            *r = saturate(red);
+            *g = saturate(green);
+            *b = saturate(blue);
+        }
+
+        adjusted
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,209 @@
+//! Reading images from archives (ZIP/CBZ and TAR/CBT) without extracting them.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, hash_map::Entry},
+    fs::{self, File},
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex, PoisonError},
+    time::{Instant, SystemTime},
+};
+
+use anyhow::anyhow;
+
+use crate::format::has_image_extension;
+
+const ZIP_EXTENSIONS: &[&str] = &["zip", "cbz"];
+const TAR_EXTENSIONS: &[&str] = &["tar", "cbt"];
+
+/// How many indexes are kept, the least recently used ones are dropped first.
+const MAX_CACHED_INDEXES: usize = 8;
+
+/// The indexes of the recently opened archives, by their path.
+static INDEXES: LazyLock<Mutex<HashMap<PathBuf, CachedIndex>>> = LazyLock::new(Mutex::default);
+
+/// Where the entries of an archive are, such that they can be read without scanning the
+/// whole archive again.
+enum Index {
+    /// The names of the entries of a ZIP archive. Its central directory is cheap to parse
+    /// again for reading an entry, hence no file handle is kept open.
+    Zip(Vec<String>),
+    /// The offset and size of the data of each file in a TAR archive.
+    Tar(HashMap<String, (u64, u64)>),
+}
+
+impl Index {
+    fn build(archive: &Path) -> anyhow::Result<Self> {
+        if has_extension(archive, ZIP_EXTENSIONS) {
+            let zip = open_zip(archive)?;
+            return Ok(Self::Zip(
+                zip.file_names().map(ToString::to_string).collect(),
+            ));
+        }
+
+        let mut tar = tar::Archive::new(File::open(archive)?);
+        let entries = tar
+            .entries_with_seek()?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.header().entry_type().is_file())
+            .filter_map(|entry| {
+                let name = entry.path().ok()?.to_string_lossy().into_owned();
+                Some((name, (entry.raw_file_position(), entry.size())))
+            })
+            .collect();
+
+        Ok(Self::Tar(entries))
+    }
+
+    fn names(&self) -> Vec<String> {
+        match self {
+            Self::Zip(names) => names.clone(),
+            Self::Tar(entries) => entries.keys().cloned().collect(),
+        }
+    }
+}
+
+/// An index together with the modification time of the archive it was built from.
+struct CachedIndex {
+    modified: Option<SystemTime>,
+    last_used: Instant,
+    index: Index,
+}
+
+/// Run `f` with the index of the archive, building it if the archive wasn't opened before
+/// or was modified since.
+///
+/// The indexes are locked while `f` runs, so it shouldn't read from the archive.
+fn with_index<T>(archive: &Path, f: impl FnOnce(&Index) -> anyhow::Result<T>) -> anyhow::Result<T> {
+    let modified = fs::metadata(archive)?.modified().ok();
+    let mut indexes = INDEXES.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let cached = match indexes.entry(archive.to_path_buf()) {
+        Entry::Occupied(entry) if entry.get().modified == modified => entry.into_mut(),
+        entry => {
+            let cached = CachedIndex {
+                modified,
+                last_used: Instant::now(),
+                index: Index::build(archive)?,
+            };
+            entry.insert_entry(cached).into_mut()
+        }
+    };
+    cached.last_used = Instant::now();
+    let result = f(&cached.index);
+
+    if indexes.len() > MAX_CACHED_INDEXES
+        && let Some(least_recently_used) = indexes
+            .iter()
+            .min_by_key(|(_, cached)| cached.last_used)
+            .map(|(path, _)| path.clone())
+    {
+        indexes.remove(&least_recently_used);
+    }
+
+    result
+}
+
+fn open_zip(archive: &Path) -> anyhow::Result<zip::ZipArchive<BufReader<File>>> {
+    Ok(zip::ZipArchive::new(BufReader::new(File::open(archive)?))?)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension().is_some_and(|ext| {
+        extensions
+            .iter()
+            .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+    })
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    has_extension(path, ZIP_EXTENSIONS) || has_extension(path, TAR_EXTENSIONS)
+}
+
+/// Compare the strings such that numbers are ordered by their value,
+/// e.g. "page2.png" comes before "page10.png".
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_char), Some(b_char)) if a_char.is_ascii_digit() && b_char.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut number = String::new();
+                    while let Some(char) = chars.next_if(char::is_ascii_digit) {
+                        number.push(char);
+                    }
+                    number
+                };
+                let a_number = take_number(&mut a_chars);
+                let b_number = take_number(&mut b_chars);
+
+                // compare without leading zeros, longer numbers are bigger
+                let a_trimmed = a_number.trim_start_matches('0');
+                let b_trimmed = b_number.trim_start_matches('0');
+                let ordering = a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(a_char), Some(b_char)) => {
+                let ordering = a_char
+                    .to_lowercase()
+                    .cmp(b_char.to_lowercase())
+                    .then_with(|| a_char.cmp(b_char));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// The names of all image entries of the archive, naturally sorted.
+pub fn list_images(archive: &Path) -> anyhow::Result<Vec<String>> {
+    let mut names = with_index(archive, |index| Ok(index.names()))?;
+
+    names.retain(|name| !name.ends_with('/') && has_image_extension(Path::new(name)));
+    names.sort_by(|a, b| natural_cmp(a, b));
+
+    Ok(names)
+}
+
+/// Read the (decompressed) contents of the entry of the archive.
+pub fn read_entry(archive: &Path, name: &str) -> anyhow::Result<Vec<u8>> {
+    let mut data = Vec::new();
+
+    let tar_entry = with_index(archive, |index| match index {
+        Index::Zip(_) => Ok(None),
+        Index::Tar(entries) => entries
+            .get(name)
+            .copied()
+            .map(Some)
+            .ok_or_else(|| anyhow!("entry \"{name}\" not found in {}", archive.display())),
+    })?;
+
+    // entries are read without holding the lock of the indexes
+    match tar_entry {
+        Some((offset, size)) => {
+            let mut file = File::open(archive)?;
+            file.seek(SeekFrom::Start(offset))?;
+            file.take(size).read_to_end(&mut data)?;
+        }
+        None => {
+            open_zip(archive)?.by_name(name)?.read_to_end(&mut data)?;
+        }
+    }
+
+    Ok(data)
+}
@@ -0,0 +1,81 @@
+//! Showing single channels of an image and checking its alpha channel, e.g. for textures
+//! and sprites.
+
+use image::{Rgba, RgbaImage};
+
+/// The color transparent parts are tinted with by [ChannelView::AlphaMask].
+const ALPHA_MASK_COLOR: [f32; 3] = [255.0, 0.0, 0.0];
+/// How strongly fully transparent parts are tinted by [ChannelView::AlphaMask].
+const ALPHA_MASK_OPACITY: f32 = 0.6;
+/// The color of pixels that can't be premultiplied in [ChannelView::Premultiplied].
+const INVALID_PREMULTIPLIED_COLOR: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// Which channels of the image are shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChannelView {
+    #[default]
+    All,
+    Red,
+    Green,
+    Blue,
+    /// The alpha channel as grayscale, white being opaque.
+    Alpha,
+    /// The opaque image with transparent parts tinted red.
+    AlphaMask,
+    /// The stored colors read as premultiplied with alpha, i.e. composited onto black.
+    /// Pixels with a channel exceeding alpha, which premultiplied images can't contain,
+    /// are highlighted in magenta.
+    Premultiplied,
+}
+
+impl ChannelView {
+    pub fn name(self) -> String {
+        match self {
+            Self::All => t!("channel.all"),
+            Self::Red => t!("channel.red"),
+            Self::Green => t!("channel.green"),
+            Self::Blue => t!("channel.blue"),
+            Self::Alpha => t!("channel.alpha"),
+            Self::AlphaMask => t!("channel.alpha_mask"),
+            Self::Premultiplied => t!("channel.premultiplied"),
+        }
+        .to_string()
+    }
+
+    /// The opaque visualization of the channel of `frame`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn apply(self, frame: &RgbaImage) -> RgbaImage {
+        let mut shown = frame.clone();
+        if self == Self::All {
+            return shown;
+        }
+
+        for pixel in shown.pixels_mut() {
+            let Rgba([r, g, b, a]) = *pixel;
+
+            *pixel = match self {
+                Self::All => *pixel,
+                Self::Red => Rgba([r, r, r, 255]),
+                Self::Green => Rgba([g, g, g, 255]),
+                Self::Blue => Rgba([b, b, b, 255]),
+                Self::Alpha => Rgba([a, a, a, 255]),
+                Self::AlphaMask => {
+                    let tint = (1.0 - f32::from(a) / 255.0) * ALPHA_MASK_OPACITY;
+                    let [r, g, b] = [r, g, b].map(f32::from);
+                    let mix = |value: f32, mask: f32| (value + (mask - value) * tint).round() as u8;
+
+                    Rgba([
+                        mix(r, ALPHA_MASK_COLOR[0]),
+                        mix(g, ALPHA_MASK_COLOR[1]),
+                        mix(b, ALPHA_MASK_COLOR[2]),
+                        255,
+                    ])
+                }
+                Self::Premultiplied if r > a || g > a || b > a => INVALID_PREMULTIPLIED_COLOR,
+                Self::Premultiplied => Rgba([r, g, b, 255]),
+            };
+        }
+
+        shown
+    }
+}
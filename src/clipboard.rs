@@ -0,0 +1,37 @@
+//! Copying images to and pasting images from the clipboard.
+
+use std::io::Cursor;
+
+use gpui::{ClipboardEntry, ClipboardItem, Image, ImageFormat};
+use image::RgbaImage;
+
+use crate::image_entry::PastedImage;
+
+/// Create a clipboard item of the image, encoded as PNG as that's understood by most apps.
+///
+/// This is potentially slow for large images, so it should be run in the background.
+pub fn image_item(buffer: &RgbaImage) -> anyhow::Result<ClipboardItem> {
+    let mut data = Vec::new();
+    buffer.write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)?;
+
+    Ok(ClipboardItem::new_image(&Image::from_bytes(
+        ImageFormat::Png,
+        data,
+    )))
+}
+
+/// The first image of the clipboard item, if any.
+pub fn pasted_image(item: &ClipboardItem) -> Option<PastedImage> {
+    item.entries().iter().find_map(|entry| match entry {
+        ClipboardEntry::Image(image) => {
+            let extension = match image.format {
+                ImageFormat::Jpeg => "jpg",
+                ImageFormat::Svg => "svg",
+                format => format.mime_type().trim_start_matches("image/"),
+            };
+
+            Some(PastedImage::new(extension, image.bytes.clone()))
+        }
+        _ => None,
+    })
+}
@@ -0,0 +1,228 @@
+//! Color management: converting images from their embedded color profile to the profile of
+//! the display, such that e.g. wide-gamut photos don't look oversaturated.
+
+use std::io::{self, Read};
+
+use anyhow::bail;
+use image::RgbaImage;
+use lcms2::{
+    CIExyY, CIExyYTRIPLE, InfoType, Intent, Locale, PixelFormat, Profile, ToneCurve, Transform,
+};
+
+use crate::config::CONFIG;
+
+/// The color profile of an image, as far as it differs from the default sRGB.
+#[derive(Clone, Debug)]
+pub enum ColorProfile {
+    /// An embedded ICC profile.
+    Icc(Vec<u8>),
+    /// The coding-independent code points of a PNG cICP chunk, see ITU-T H.273.
+    Cicp { primaries: u8, transfer: u8 },
+    /// The (decoding) gamma of a PNG gAMA chunk.
+    Gamma(f64),
+}
+
+impl ColorProfile {
+    /// A human readable name of the profile, e.g. "Display P3".
+    pub fn name(&self) -> String {
+        match self {
+            Self::Icc(data) => Profile::new_icc(data)
+                .ok()
+                .and_then(|profile| profile.info(InfoType::Description, Locale::none()))
+                .unwrap_or_else(|| "ICC".to_string()),
+            Self::Cicp { primaries, .. } => match primaries {
+                1 => "sRGB (cICP)".to_string(),
+                9 => "Rec. 2020 (cICP)".to_string(),
+                12 => "Display P3 (cICP)".to_string(),
+                primaries => format!("cICP {primaries}"),
+            },
+            Self::Gamma(gamma) => format!("Gamma {gamma:.2}"),
+        }
+    }
+
+    fn to_lcms(&self) -> anyhow::Result<Profile> {
+        match self {
+            Self::Icc(data) => Ok(Profile::new_icc(data)?),
+            Self::Cicp {
+                primaries,
+                transfer,
+            } => {
+                let curve = match transfer {
+                    13 => srgb_curve()?,
+                    1 | 6 | 14 | 15 => rec709_curve()?,
+                    4 => ToneCurve::new(2.2),
+                    5 => ToneCurve::new(2.8),
+                    8 => ToneCurve::new(1.0),
+                    // e.g. the HDR transfer functions PQ and HLG
+                    transfer => bail!("unsupported cICP transfer characteristics {transfer}"),
+                };
+
+                Ok(Profile::new_rgb(
+                    &D65_WHITE_POINT,
+                    &cicp_primaries(*primaries)?,
+                    &[&curve, &curve, &curve],
+                )?)
+            }
+            Self::Gamma(gamma) => {
+                let curve = ToneCurve::new(*gamma);
+
+                Ok(Profile::new_rgb(
+                    &D65_WHITE_POINT,
+                    &SRGB_PRIMARIES,
+                    &[&curve, &curve, &curve],
+                )?)
+            }
+        }
+    }
+}
+
+const D65_WHITE_POINT: CIExyY = xy(0.3127, 0.3290);
+
+const SRGB_PRIMARIES: CIExyYTRIPLE = CIExyYTRIPLE {
+    Red: xy(0.64, 0.33),
+    Green: xy(0.30, 0.60),
+    Blue: xy(0.15, 0.06),
+};
+
+const fn xy(x: f64, y: f64) -> CIExyY {
+    CIExyY { x, y, Y: 1.0 }
+}
+
+fn cicp_primaries(primaries: u8) -> anyhow::Result<CIExyYTRIPLE> {
+    Ok(match primaries {
+        1 => SRGB_PRIMARIES,
+        9 => CIExyYTRIPLE {
+            Red: xy(0.708, 0.292),
+            Green: xy(0.170, 0.797),
+            Blue: xy(0.131, 0.046),
+        },
+        12 => CIExyYTRIPLE {
+            Red: xy(0.680, 0.320),
+            Green: xy(0.265, 0.690),
+            Blue: xy(0.150, 0.060),
+        },
+        primaries => bail!("unsupported cICP color primaries {primaries}"),
+    })
+}
+
+fn srgb_curve() -> anyhow::Result<ToneCurve> {
+    Ok(ToneCurve::new_parametric(
+        4,
+        &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045],
+    )?)
+}
+
+fn rec709_curve() -> anyhow::Result<ToneCurve> {
+    Ok(ToneCurve::new_parametric(
+        4,
+        &[1.0 / 0.45, 1.0 / 1.099, 0.099 / 1.099, 1.0 / 4.5, 0.081],
+    )?)
+}
+
+/// The configured display profile, sRGB by default.
+fn display_profile() -> anyhow::Result<Profile> {
+    match &CONFIG.get().unwrap().display_profile {
+        Some(path) => Ok(Profile::new_file(path)?),
+        None => Ok(Profile::new_srgb()),
+    }
+}
+
+/// Convert the pixels from the color profile of the image to the display profile.
+pub fn convert_to_display<'a>(
+    buffers: impl IntoIterator<Item = &'a mut RgbaImage>,
+    profile: &ColorProfile,
+) -> anyhow::Result<()> {
+    let transform: Transform<[u8; 4], [u8; 4]> = Transform::new(
+        &profile.to_lcms()?,
+        PixelFormat::RGBA_8,
+        &display_profile()?,
+        PixelFormat::RGBA_8,
+        Intent::Perceptual,
+    )?;
+
+    for buffer in buffers {
+        transform.transform_in_place(bytemuck::cast_slice_mut(buffer.as_mut()));
+    }
+
+    Ok(())
+}
+
+/// The color related chunks of a PNG, which precede the image data.
+#[derive(Default)]
+pub struct PngColorChunks {
+    cicp: Option<(u8, u8)>,
+    srgb: bool,
+    gamma: Option<u32>,
+}
+
+impl PngColorChunks {
+    /// Read the chunks, stopping at the image data.
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut chunks = Self::default();
+
+        let mut signature = [0; 8];
+        reader.read_exact(&mut signature)?;
+
+        loop {
+            let mut header = [0; 8];
+            reader.read_exact(&mut header)?;
+            let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+            let chunk_type = &header[4..];
+
+            match chunk_type {
+                b"IDAT" | b"IEND" => return Ok(chunks),
+                b"cICP" | b"gAMA" if length == 4 => {
+                    let mut data = [0; 4];
+                    reader.read_exact(&mut data)?;
+
+                    if chunk_type == b"cICP" {
+                        chunks.cicp = Some((data[0], data[1]));
+                    } else {
+                        chunks.gamma = Some(u32::from_be_bytes(data));
+                    }
+                }
+                b"sRGB" => {
+                    chunks.srgb = true;
+                    io::copy(
+                        &mut reader.by_ref().take(u64::from(length)),
+                        &mut io::sink(),
+                    )?;
+                }
+                _ => {
+                    io::copy(
+                        &mut reader.by_ref().take(u64::from(length)),
+                        &mut io::sink(),
+                    )?;
+                }
+            }
+
+            // skip the CRC
+            io::copy(&mut reader.by_ref().take(4), &mut io::sink())?;
+        }
+    }
+
+    /// The color profile of the PNG, `icc` being the profile of the iCCP chunk.
+    ///
+    /// The chunks take precedence in the order cICP, iCCP, sRGB, gAMA.
+    pub fn profile(self, icc: Option<Vec<u8>>) -> Option<ColorProfile> {
+        if let Some((primaries, transfer)) = self.cicp {
+            return Some(ColorProfile::Cicp {
+                primaries,
+                transfer,
+            });
+        }
+
+        if let Some(icc) = icc {
+            return Some(ColorProfile::Icc(icc));
+        }
+
+        if self.srgb {
+            return None;
+        }
+
+        // the chunk contains the encoding gamma times 100000
+        self.gamma
+            .filter(|gamma| *gamma > 0)
+            .map(|gamma| ColorProfile::Gamma(100_000.0 / f64::from(gamma)))
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::anyhow;
+
+/// The state of the app that can be inserted into a [crate::config::CustomCommand].
+pub struct CommandContext<'a> {
+    /// The currently shown image.
+    pub current_image: Option<&'a Path>,
+    /// The marked images, or the current image if none are marked.
+    pub marked_images: &'a [PathBuf],
+    /// Index of the currently shown image, starting at 0.
+    pub index: usize,
+}
+
+/// Quote the string so that `sh` treats it as a single word.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Replace all placeholders (e.g. '%f') in the command line with their (quoted) values.
+///
+/// See [crate::config::CustomCommand::command] for the supported placeholders.
+pub fn expand_placeholders(command: &str, context: &CommandContext) -> String {
+    let mut expanded = String::with_capacity(command.len());
+    let mut chars = command.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '%' {
+            expanded.push(char);
+            continue;
+        }
+
+        match chars.next() {
+            Some('f') => {
+                if let Some(path) = context.current_image {
+                    expanded.push_str(&shell_quote(&path.to_string_lossy()));
+                }
+            }
+            Some('F') => {
+                let paths = context
+                    .marked_images
+                    .iter()
+                    .map(|path| shell_quote(&path.to_string_lossy()))
+                    .collect::<Vec<_>>();
+                expanded.push_str(&paths.join(" "));
+            }
+            Some('i') => expanded.push_str(&(context.index + 1).to_string()),
+            Some('d') => {
+                if let Some(dir) = context.current_image.and_then(Path::parent) {
+                    expanded.push_str(&shell_quote(&dir.to_string_lossy()));
+                }
+            }
+            Some('%') => expanded.push('%'),
+            // unknown placeholder, keep it as is
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+
+    expanded
+}
+
+/// Run the command line with `sh -c` and wait for it to finish.
+///
+/// If the command fails, the returned error contains its stderr output.
+pub fn run(command_line: &str) -> anyhow::Result<()> {
+    let output = Command::new("sh").arg("-c").arg(command_line).output()?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.is_empty() {
+        Err(anyhow!("{}", output.status))
+    } else {
+        Err(anyhow!(stderr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What `sh` makes of the quoted value, which has to be the value itself.
+    fn unquoted(quoted: &str) -> String {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {quoted}"))
+            .output()
+            .unwrap();
+
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    fn context<'a>(current_image: &'a Path, marked_images: &'a [PathBuf]) -> CommandContext<'a> {
+        CommandContext {
+            current_image: Some(current_image),
+            marked_images,
+            index: 0,
+        }
+    }
+
+    #[test]
+    fn quotes_in_paths() {
+        let path = Path::new("/tmp/it's '; rm -rf ~; '.png");
+        let expanded = expand_placeholders("%f", &context(path, &[]));
+
+        assert_eq!(expanded, r"'/tmp/it'\''s '\''; rm -rf ~; '\''.png'");
+        assert_eq!(unquoted(&expanded), path.to_str().unwrap());
+    }
+
+    #[test]
+    fn literal_percent() {
+        let expanded = expand_placeholders("echo 100%% %q %", &context(Path::new("a"), &[]));
+
+        assert_eq!(expanded, "echo 100% %q %");
+    }
+
+    #[test]
+    fn marked_images() {
+        let marked = [PathBuf::from("/a b.png"), PathBuf::from("/c'd.png")];
+        let expanded = expand_placeholders("cp %F %d", &context(Path::new("/x/e.png"), &marked));
+
+        assert_eq!(expanded, r"cp '/a b.png' '/c'\''d.png' '/x'");
+        assert_eq!(unquoted("'/a b.png'"), "/a b.png");
+    }
+
+    #[test]
+    fn one_based_index() {
+        let mut context = context(Path::new("a"), &[]);
+        context.index = 4;
+
+        assert_eq!(expand_placeholders("%i", &context), "5");
+    }
+
+    #[test]
+    fn missing_image() {
+        let context = CommandContext {
+            current_image: None,
+            marked_images: &[],
+            index: 0,
+        };
+
+        assert_eq!(expand_placeholders("open %f %d", &context), "open  ");
+    }
+}
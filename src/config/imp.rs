@@ -13,7 +13,9 @@ use gpui::Rgba;
 
 use super::Color;
 use crate::actions::*;
+use crate::archive;
 use crate::config::ArgsWithConfig;
+use crate::config::CliCommand;
 use crate::config::Config;
 use crate::config::KeyBinding;
 
@@ -29,6 +31,16 @@ impl KeyBinding {
     }
 }
 
+/// Options that can only be provided via the command line.
+pub struct CliOptions {
+    /// Paths to the images that should be opened.
+    pub images: Vec<PathBuf>,
+    /// Whether to run as an image picker, see [ArgsWithConfig::pick].
+    pub pick: bool,
+    /// The subcommand to run instead of opening a window.
+    pub command: Option<CliCommand>,
+}
+
 fn read_paths_from_stdin() -> Vec<PathBuf> {
     let stdin = io::stdin();
     let mut handle = stdin.lock();
@@ -68,16 +80,20 @@ fn read_config_file(config_path_override: Option<&Path>) -> anyhow::Result<Confi
 
 /// Validate the configuration.
 ///
-/// This especially ensures that no key is assigned to multiple actions.
+/// This especially ensures that no key is assigned to multiple actions or commands.
 fn validate_config(config: &Config) -> anyhow::Result<()> {
-    let binding_keys = config.keybindings.iter().map(|binding| &binding.key);
+    let binding_keys = config
+        .keybindings
+        .iter()
+        .map(|binding| &binding.key)
+        .chain(config.commands.iter().map(|command| &command.key));
 
     // Find duplicated keys, i.e. one key is assigned to multiple actions
     let mut unique_keys = HashSet::new();
     for key in binding_keys {
         if !unique_keys.insert(key) {
             return Err(anyhow!(
-                "[keybindings] - key \"{key}\" is assigned to multiple actions."
+                "[keybindings] - key \"{key}\" is assigned to multiple actions or commands."
             ));
         }
     }
@@ -87,7 +103,7 @@ fn validate_config(config: &Config) -> anyhow::Result<()> {
 
 /// Parse the CLI arguments and fall back to the config file for all arguments
 /// that were not provided.
-pub fn parse_cli_args_with_config() -> anyhow::Result<(Vec<PathBuf>, Config)> {
+pub fn parse_cli_args_with_config() -> anyhow::Result<(CliOptions, Config)> {
     let mut args = ArgsWithConfig::parse();
     let config = read_config_file(args.config_path.as_deref())?;
 
@@ -99,7 +115,82 @@ pub fn parse_cli_args_with_config() -> anyhow::Result<(Vec<PathBuf>, Config)> {
     // merge with the provided CLI arguments
     let config = config.merge(&mut args.config);
 
-    Ok((args.images, config))
+    let cli_options = CliOptions {
+        images: args.images,
+        pick: args.pick,
+        command: args.command,
+    };
+
+    Ok((cli_options, config))
+}
+
+/// Make the paths absolute, relative to the current work directory.
+pub fn absolute_paths(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+
+    paths
+        .iter()
+        .map(|path| {
+            if path.is_relative() {
+                cwd.join(path)
+            } else {
+                path.clone()
+            }
+        })
+        .collect()
+}
+
+/// Make the paths absolute (relative to the current work directory), replace directories
+/// with the images they contain and remove all other paths that are not files.
+pub fn resolve_image_paths(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let paths = absolute_paths(paths)
+        .into_iter()
+        .flat_map(|path| {
+            if path.is_dir() {
+                images_in_directory(&path)
+            } else {
+                vec![path]
+            }
+        })
+        .filter(|path| path.is_file())
+        .collect();
+
+    #[cfg(feature = "raw")]
+    if CONFIG
+        .get()
+        .is_some_and(|config| config.merge_raw_jpeg_pairs)
+    {
+        return crate::raw::merge_raw_jpeg_pairs(paths);
+    }
+
+    paths
+}
+
+/// The images (and archives) directly inside the directory, naturally sorted by their name.
+fn images_in_directory(directory: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+
+    let mut images: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let is_image = crate::format::has_image_extension(path) || archive::is_archive(path);
+            #[cfg(feature = "raw")]
+            let is_image = is_image || crate::raw::is_raw(path);
+
+            is_image
+        })
+        .collect();
+    images.sort_by(|a, b| {
+        archive::natural_cmp(
+            &a.file_name().unwrap_or_default().to_string_lossy(),
+            &b.file_name().unwrap_or_default().to_string_lossy(),
+        )
+    });
+
+    images
 }
 
 pub fn get_styles() -> clap::builder::Styles {
@@ -16,7 +16,7 @@ pub mod imp;
 #[cfg(feature = "build-time")]
 pub use imp::CONFIG;
 #[cfg(feature = "build-time")]
-pub use imp::parse_cli_args_with_config;
+pub use imp::{absolute_paths, parse_cli_args_with_config, resolve_image_paths};
 
 /// Display images from files.
 ///
@@ -33,11 +33,59 @@ struct ArgsWithConfig {
     #[clap(short, long = "config")]
     config_path: Option<std::path::PathBuf>,
 
+    /// Run as an image picker for scripts.
+    ///
+    /// Picking prints the paths of the marked images (or the current image if none are marked)
+    /// to stdout and exits with status 0. Closing the window exits with status 2 instead.
+    #[clap(long)]
+    pick: bool,
+
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
     /// Arguments that are configurable via the config file as well
     #[command(flatten)]
     config: <Config as ClapSerde>::Opt,
 }
 
+#[derive(clap::Subcommand)]
+pub enum CliCommand {
+    /// Send a message to a running instance via its control socket and print the response(s).
+    ///
+    /// The message is either a raw JSON request, e.g. '{"command":"get-current"}', or one of
+    /// the shorthands 'action <name>', 'open <paths>', 'append <paths>', 'get-current' and 'subscribe'.
+    ///
+    /// Actions with an argument are named '<name>:<argument>', i.e. 'runcommand:<index>' for the
+    /// custom command at the index (starting at 0) and 'togglehistogramchannel:<channel>' for
+    /// the channels 'red', 'green', 'blue' and 'luminance'.
+    Msg {
+        /// Path to the control socket. Defaults to the configured socket path,
+        /// or the default socket in single-instance mode.
+        #[clap(short, long)]
+        socket: Option<std::path::PathBuf>,
+        /// The message to send.
+        #[clap(required = true)]
+        message: Vec<String>,
+    },
+    /// Compare two images of the same size and print their PSNR, SSIM and maximum pixel delta.
+    ///
+    /// Exits with status 1 if the images differ more than the thresholds allow (by default,
+    /// if they differ at all), and with status 2 if they couldn't be compared.
+    Diff {
+        first: std::path::PathBuf,
+        second: std::path::PathBuf,
+        /// The maximum allowed difference of a single channel of a single pixel.
+        #[clap(long)]
+        max_delta: Option<u8>,
+        /// The minimum allowed peak signal-to-noise ratio in dB.
+        #[clap(long)]
+        min_psnr: Option<f64>,
+        /// The minimum allowed structural similarity, between 0 and 1.
+        #[clap(long)]
+        min_ssim: Option<f64>,
+    },
+}
+
 #[derive(ClapSerde, Serialize, Deserialize, Debug)]
 pub struct Config {
     /// Whether to make the window transparent.
@@ -48,10 +96,113 @@ pub struct Config {
     #[arg(short, long, action = ArgAction::SetTrue)]
     #[serde(default)]
     pub fullscreen: bool,
+    /// Whether to stop animations after playing them once instead of looping them.
+    #[arg(long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub no_animation_loop: bool,
+    /// Whether to only show the JPEG file of RAW+JPEG pairs with the same file name.
+    ///
+    /// Only has an effect if RAW support is enabled.
+    #[arg(long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub merge_raw_jpeg_pairs: bool,
+    /// Maximum width and height in pixels that vector images (SVGs) are rasterized at when zooming.
+    #[default(8192)]
+    #[arg(long)]
+    #[serde(default = "Config::max_svg_raster_size_default")]
+    pub max_svg_raster_size: u32,
+    /// Whether to show two consecutive images side by side, like the pages of a book.
+    #[arg(long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub spread: bool,
+    /// Whether to show the pages of a spread from right to left, e.g. for manga.
+    #[arg(long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub right_to_left: bool,
+    /// Whether to show the first image on its own in spread mode, e.g. the cover of a comic.
+    #[arg(long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub single_cover: bool,
+    /// Whether to stack all images in a vertically scrollable column, e.g. for long-strip comics.
+    #[arg(long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub continuous_scroll: bool,
+    /// Whether files dropped onto the window replace the current images instead of being appended.
+    #[arg(long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub drop_replaces: bool,
+    /// Whether to show images without converting them from their embedded color profile.
+    #[arg(long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub no_color_management: bool,
+    /// Path of the ICC profile of the display, sRGB is assumed if not provided.
+    #[arg(long)]
+    #[serde(default)]
+    pub display_profile: Option<std::path::PathBuf>,
+    /// Factor the differences of compared images are multiplied with in the amplified
+    /// difference mode.
+    #[default(10.0)]
+    #[arg(long)]
+    #[serde(default = "Config::difference_amplification_default")]
+    pub difference_amplification: f32,
+    /// Milliseconds between switching the compared images in flicker mode.
+    #[default(500)]
+    #[arg(long)]
+    #[serde(default = "Config::flicker_interval_default")]
+    pub flicker_interval: u64,
+    /// Stops (EV) the exposure of the shown image changes by per step.
+    #[default(0.25)]
+    #[arg(long)]
+    #[serde(default = "Config::exposure_step_default")]
+    pub exposure_step: f32,
+    /// How much the contrast of the shown image changes per step.
+    #[default(0.1)]
+    #[arg(long)]
+    #[serde(default = "Config::contrast_step_default")]
+    pub contrast_step: f32,
+    /// How much the gamma of the shown image changes per step.
+    #[default(0.1)]
+    #[arg(long)]
+    #[serde(default = "Config::gamma_step_default")]
+    pub gamma_step: f32,
+    /// How much the saturation of the shown image changes per step.
+    #[default(0.1)]
+    #[arg(long)]
+    #[serde(default = "Config::saturation_step_default")]
+    pub saturation_step: f32,
+    /// How images are sampled when zoomed in.
+    ///
+    /// Nearest-neighbour sampling upscales the image to at most 16 megapixels (all frames of
+    /// animations together), larger images are sampled smoothly.
+    #[default(Sampling::Auto)]
+    #[arg(long, value_enum)]
+    #[serde(default = "Config::sampling_default")]
+    pub sampling: Sampling,
+    /// How many screen pixels an image pixel has to span for the automatic sampling mode to
+    /// switch to nearest-neighbour sampling.
+    #[default(4.0)]
+    #[arg(long)]
+    #[serde(default = "Config::nearest_sampling_threshold_default")]
+    pub nearest_sampling_threshold: f32,
+    /// How many screen pixels an image pixel has to span for the pixel grid to appear.
+    #[default(16.0)]
+    #[arg(long)]
+    #[serde(default = "Config::pixel_grid_threshold_default")]
+    pub pixel_grid_threshold: f32,
     /// App ID - specifically useful for styling the app's window via desktop environments.
     #[arg(long)]
     #[serde(default)]
     pub app_id: Option<String>,
+    /// Path of a unix socket for remotely controlling the app, disabled if not provided.
+    #[arg(long)]
+    #[serde(default)]
+    pub socket_path: Option<std::path::PathBuf>,
+    /// Whether to pass the images to an already running instance instead of opening a new window.
+    ///
+    /// Uses the socket path if provided, otherwise a socket in $XDG_RUNTIME_DIR.
+    #[arg(short, long, action = ArgAction::SetTrue)]
+    #[serde(default)]
+    pub single_instance: bool,
     /// Theme config.
     #[command(flatten)]
     #[serde(default)]
@@ -60,6 +211,10 @@ pub struct Config {
     #[arg(skip)]
     #[serde(default)]
     pub keybindings: Vec<KeyBinding>,
+    /// Custom external commands.
+    #[arg(skip)]
+    #[serde(default)]
+    pub commands: Vec<CustomCommand>,
 }
 
 #[derive(Serialize, Deserialize, Debug, clap::Args)]
@@ -86,6 +241,86 @@ pub struct ThemeConfig {
     #[arg(long = "error-color", default_value_t = ThemeConfig::error_default())]
     #[serde(default = "ThemeConfig::error_default")]
     pub error: Color,
+
+    /// Red color, e.g. of the red channel in the histogram.
+    #[arg(long = "red-color", default_value_t = ThemeConfig::red_default())]
+    #[serde(default = "ThemeConfig::red_default")]
+    pub red: Color,
+    /// Green color, e.g. of the green channel in the histogram.
+    #[arg(long = "green-color", default_value_t = ThemeConfig::green_default())]
+    #[serde(default = "ThemeConfig::green_default")]
+    pub green: Color,
+    /// Blue color, e.g. of the blue channel in the histogram.
+    #[arg(long = "blue-color", default_value_t = ThemeConfig::blue_default())]
+    #[serde(default = "ThemeConfig::blue_default")]
+    pub blue: Color,
+
+    /// What is shown behind (partially) transparent images.
+    #[arg(long, value_enum, default_value_t = ThemeConfig::backdrop_default())]
+    #[serde(default = "ThemeConfig::backdrop_default")]
+    pub backdrop: Backdrop,
+    /// Color of the solid backdrop.
+    #[arg(long = "backdrop-color", default_value_t = ThemeConfig::backdrop_color_default())]
+    #[serde(default = "ThemeConfig::backdrop_color_default")]
+    pub backdrop_color: Color,
+    /// Color of the light cells of the checkerboard backdrop.
+    #[arg(long = "checkerboard-light-color", default_value_t = ThemeConfig::checkerboard_light_default())]
+    #[serde(default = "ThemeConfig::checkerboard_light_default")]
+    pub checkerboard_light: Color,
+    /// Color of the dark cells of the checkerboard backdrop.
+    #[arg(long = "checkerboard-dark-color", default_value_t = ThemeConfig::checkerboard_dark_default())]
+    #[serde(default = "ThemeConfig::checkerboard_dark_default")]
+    pub checkerboard_dark: Color,
+    /// Size of the cells of the checkerboard backdrop in pixels.
+    #[arg(long, default_value_t = ThemeConfig::checkerboard_size_default())]
+    #[serde(default = "ThemeConfig::checkerboard_size_default")]
+    pub checkerboard_size: u32,
+}
+
+/// What is shown behind (partially) transparent images.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backdrop {
+    /// A checkerboard pattern, making transparent areas easy to spot.
+    Checkerboard,
+    /// A single color.
+    Solid,
+    /// Nothing, i.e. the window background (which might be transparent) shines through.
+    Transparent,
+}
+
+impl Backdrop {
+    /// The next backdrop, for cycling through all of them.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Checkerboard => Self::Solid,
+            Self::Solid => Self::Transparent,
+            Self::Transparent => Self::Checkerboard,
+        }
+    }
+}
+
+/// How images are sampled when zoomed in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sampling {
+    /// Interpolate between pixels, which suits photos.
+    Smooth,
+    /// Show each pixel as a sharp square, which suits pixel art.
+    Nearest,
+    /// Smooth sampling, switching to nearest-neighbour sampling above the configured zoom.
+    Auto,
+}
+
+impl Sampling {
+    /// The next sampling mode, for cycling through all of them.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Smooth => Self::Nearest,
+            Self::Nearest => Self::Auto,
+            Self::Auto => Self::Smooth,
+        }
+    }
 }
 
 impl Default for ThemeConfig {
@@ -97,6 +332,14 @@ impl Default for ThemeConfig {
             surface: Color::from_str("#6c708688").unwrap(),
             primary: Color::from_str("#cba6f7").unwrap(),
             error: Color::from_str("#d20f39").unwrap(),
+            red: Color::from_str("#f38ba8").unwrap(),
+            green: Color::from_str("#a6e3a1").unwrap(),
+            blue: Color::from_str("#89b4fa").unwrap(),
+            backdrop: Backdrop::Checkerboard,
+            backdrop_color: Color::from_str("#1e1e2e").unwrap(),
+            checkerboard_light: Color::from_str("#585b70").unwrap(),
+            checkerboard_dark: Color::from_str("#45475a").unwrap(),
+            checkerboard_size: 16,
         }
     }
 }
@@ -118,14 +361,53 @@ pub struct KeyBinding {
     pub action: String,
 }
 
+/// An external command that is triggered by a keyboard shortcut.
+///
+/// Example: `{ key = "ctrl-w", command = "swww img %f" }`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CustomCommand {
+    /// The keyboard shortcut that should run the command, see [KeyBinding::key].
+    pub key: String,
+    /// The command line, executed by `sh -c`.
+    ///
+    /// Supported placeholders:
+    /// - '%f': path of the current image
+    /// - '%F': paths of the marked images (or the current image if none are marked)
+    /// - '%i': index of the current image, starting at 1
+    /// - '%d': directory of the current image
+    /// - '%%': a literal '%'
+    pub command: String,
+    /// Whether to reload the list of images after the command finished successfully.
+    #[serde(default)]
+    pub reload: bool,
+}
+
 // Automatically generate default getter methods to be used with #[serde(default = ...)]
 // and #[arg(default_value_t = ...)]
 //
 // This avoids having to re-define the default theme colors in multiple places.
 //
 // Could possibly be removed if https://github.com/clap-rs/clap/issues/3116 is implemented
+make_default_value_getter!(Config, max_svg_raster_size, u32);
+make_default_value_getter!(Config, difference_amplification, f32);
+make_default_value_getter!(Config, flicker_interval, u64);
+make_default_value_getter!(Config, exposure_step, f32);
+make_default_value_getter!(Config, contrast_step, f32);
+make_default_value_getter!(Config, gamma_step, f32);
+make_default_value_getter!(Config, saturation_step, f32);
+make_default_value_getter!(Config, sampling, Sampling);
+make_default_value_getter!(Config, nearest_sampling_threshold, f32);
+make_default_value_getter!(Config, pixel_grid_threshold, f32);
 make_default_value_getter!(ThemeConfig, background, Color);
 make_default_value_getter!(ThemeConfig, foreground, Color);
 make_default_value_getter!(ThemeConfig, surface, Color);
 make_default_value_getter!(ThemeConfig, primary, Color);
 make_default_value_getter!(ThemeConfig, error, Color);
+make_default_value_getter!(ThemeConfig, red, Color);
+make_default_value_getter!(ThemeConfig, green, Color);
+make_default_value_getter!(ThemeConfig, blue, Color);
+make_default_value_getter!(ThemeConfig, backdrop, Backdrop);
+make_default_value_getter!(ThemeConfig, backdrop_color, Color);
+make_default_value_getter!(ThemeConfig, checkerboard_light, Color);
+make_default_value_getter!(ThemeConfig, checkerboard_dark, Color);
+make_default_value_getter!(ThemeConfig, checkerboard_size, u32);
@@ -0,0 +1,488 @@
+//! Selecting a rectangle of an image to crop it to, either by dragging or by typing
+//! its position and size.
+
+use image::RgbaImage;
+
+/// A rectangle of an image in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRect {
+    /// The rectangle scaled from an image of size `from` to an image of size `to`,
+    /// `None` if `to` is empty.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn scale(self, from: (u32, u32), to: (u32, u32)) -> Option<Self> {
+        if to.0 == 0 || to.1 == 0 {
+            return None;
+        }
+        if from == to {
+            return Some(self);
+        }
+
+        let scale = |value: u32, from: u32, to: u32| {
+            (value as f32 * to as f32 / from as f32).round() as u32
+        };
+        let x = scale(self.x, from.0, to.0).min(to.0.saturating_sub(1));
+        let y = scale(self.y, from.1, to.1).min(to.1.saturating_sub(1));
+
+        Some(Self {
+            x,
+            y,
+            width: scale(self.width, from.0, to.0).clamp(1, to.0 - x),
+            height: scale(self.height, from.1, to.1).clamp(1, to.1 - y),
+        })
+    }
+}
+
+/// Presets for the aspect ratio of the crop rectangle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AspectRatio {
+    #[default]
+    Free,
+    /// The aspect ratio of the image itself.
+    Original,
+    Square,
+    FourThree,
+    ThreeTwo,
+    SixteenNine,
+}
+
+impl AspectRatio {
+    fn next(self) -> Self {
+        match self {
+            Self::Free => Self::Original,
+            Self::Original => Self::Square,
+            Self::Square => Self::FourThree,
+            Self::FourThree => Self::ThreeTwo,
+            Self::ThreeTwo => Self::SixteenNine,
+            Self::SixteenNine => Self::Free,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::Free => t!("crop.free").to_string(),
+            Self::Original => t!("crop.original").to_string(),
+            Self::Square => "1:1".to_string(),
+            Self::FourThree => "4:3".to_string(),
+            Self::ThreeTwo => "3:2".to_string(),
+            Self::SixteenNine => "16:9".to_string(),
+        }
+    }
+
+    /// Width divided by height, `None` if the rectangle may have any aspect ratio.
+    #[allow(clippy::cast_precision_loss)]
+    fn ratio(self, (width, height): (u32, u32)) -> Option<f32> {
+        match self {
+            Self::Free => None,
+            Self::Original => Some(width as f32 / height as f32),
+            Self::Square => Some(1.0),
+            Self::FourThree => Some(4.0 / 3.0),
+            Self::ThreeTwo => Some(3.0 / 2.0),
+            Self::SixteenNine => Some(16.0 / 9.0),
+        }
+    }
+}
+
+/// The values of the crop rectangle that can be typed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CropField {
+    X,
+    Y,
+    Width,
+    Height,
+}
+
+impl CropField {
+    pub const ALL: [Self; 4] = [Self::X, Self::Y, Self::Width, Self::Height];
+
+    /// The field edited after this one, `None` after the last one.
+    fn next(self) -> Option<Self> {
+        match self {
+            Self::X => Some(Self::Y),
+            Self::Y => Some(Self::Width),
+            Self::Width => Some(Self::Height),
+            Self::Height => None,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::X => t!("crop.x"),
+            Self::Y => t!("crop.y"),
+            Self::Width => t!("crop.width"),
+            Self::Height => t!("crop.height"),
+        }
+        .to_string()
+    }
+
+    pub fn value(self, rect: CropRect) -> u32 {
+        match self {
+            Self::X => rect.x,
+            Self::Y => rect.y,
+            Self::Width => rect.width,
+            Self::Height => rect.height,
+        }
+    }
+}
+
+/// The crop rectangle of an image and how it's being edited.
+#[derive(Clone, Debug)]
+pub struct Crop {
+    pub rect: Option<CropRect>,
+    pub aspect_ratio: AspectRatio,
+    /// Whether only the selected part of the image is shown.
+    pub preview: bool,
+    /// The field that is edited by typing, and the digits typed so far.
+    pub editing: Option<(CropField, String)>,
+    image_size: (u32, u32),
+    drag_start: Option<(u32, u32)>,
+}
+
+impl Crop {
+    pub fn new(image_size: (u32, u32)) -> Self {
+        Self {
+            rect: None,
+            aspect_ratio: AspectRatio::default(),
+            preview: false,
+            editing: None,
+            image_size,
+            drag_start: None,
+        }
+    }
+
+    /// The size of the image in pixels, which the crop rectangle refers to.
+    pub fn image_size(&self) -> (u32, u32) {
+        self.image_size
+    }
+
+    /// Start selecting a new rectangle at `position`, given in image pixels.
+    pub fn start_drag(&mut self, position: (u32, u32)) {
+        self.drag_start = Some(position);
+        self.rect = None;
+        self.editing = None;
+    }
+
+    /// Span the rectangle from where the drag started to `position`,
+    /// as far as possible with the aspect ratio.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn drag(&mut self, position: (u32, u32)) {
+        let Some(start) = self.drag_start else {
+            return;
+        };
+
+        let (mut width, mut height) = (start.0.abs_diff(position.0), start.1.abs_diff(position.1));
+        if let Some(ratio) = self.aspect_ratio.ratio(self.image_size) {
+            // the room left in the direction of the drag
+            let max_width = if position.0 < start.0 {
+                start.0
+            } else {
+                self.image_size.0 - start.0
+            };
+            let max_height = if position.1 < start.1 {
+                start.1
+            } else {
+                self.image_size.1 - start.1
+            };
+
+            width = width
+                .max((height as f32 * ratio).round() as u32)
+                .min(max_width);
+            height = ((width as f32 / ratio).round() as u32).min(max_height);
+            width = width.min((height as f32 * ratio).round() as u32);
+        }
+
+        self.rect = (width > 0 && height > 0).then(|| CropRect {
+            x: if position.0 < start.0 {
+                start.0 - width
+            } else {
+                start.0
+            },
+            y: if position.1 < start.1 {
+                start.1 - height
+            } else {
+                start.1
+            },
+            width,
+            height,
+        });
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag_start = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag_start.is_some()
+    }
+
+    /// Switch to the next aspect ratio preset and shrink the rectangle to match it.
+    pub fn cycle_aspect_ratio(&mut self) {
+        self.aspect_ratio = self.aspect_ratio.next();
+
+        if let Some(rect) = self.rect {
+            self.rect = Some(self.fit_aspect_ratio(rect, CropField::Width));
+        }
+    }
+
+    /// Adjust the height (or the width if `changed` is the height) to the aspect ratio,
+    /// keeping the rectangle within the image.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn fit_aspect_ratio(&self, mut rect: CropRect, changed: CropField) -> CropRect {
+        let Some(ratio) = self.aspect_ratio.ratio(self.image_size) else {
+            return rect;
+        };
+        let max_width = self.image_size.0 - rect.x;
+        let max_height = self.image_size.1 - rect.y;
+
+        if changed == CropField::Height {
+            rect.width = ((rect.height as f32 * ratio).round() as u32).clamp(1, max_width);
+        }
+        rect.height = ((rect.width as f32 / ratio).round() as u32).clamp(1, max_height);
+        rect.width = ((rect.height as f32 * ratio).round() as u32).clamp(1, max_width);
+
+        rect
+    }
+
+    /// Handle a key typed while cropping, returning whether it was used.
+    ///
+    /// Tab moves through the fields (selecting the whole image if nothing is selected yet),
+    /// digits and backspace edit the current field.
+    pub fn type_key(&mut self, key: &str) -> bool {
+        match key {
+            "tab" => {
+                let next = match &self.editing {
+                    Some((field, _)) => field.next(),
+                    None => Some(CropField::X),
+                };
+                self.editing = next.map(|field| (field, String::new()));
+                if self.rect.is_none() {
+                    self.rect = Some(CropRect {
+                        x: 0,
+                        y: 0,
+                        width: self.image_size.0,
+                        height: self.image_size.1,
+                    });
+                }
+
+                true
+            }
+            "backspace" => {
+                let Some((_, digits)) = &mut self.editing else {
+                    return false;
+                };
+                digits.pop();
+                self.apply_typed_value();
+
+                true
+            }
+            digit if digit.len() == 1 && digit.chars().all(|c| c.is_ascii_digit()) => {
+                let Some((_, digits)) = &mut self.editing else {
+                    return false;
+                };
+                digits.push_str(digit);
+                self.apply_typed_value();
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn apply_typed_value(&mut self) {
+        let (Some((field, digits)), Some(mut rect)) = (&self.editing, self.rect) else {
+            return;
+        };
+        let Ok(value) = digits.parse::<u32>() else {
+            return;
+        };
+        let (width, height) = self.image_size;
+
+        match field {
+            CropField::X => {
+                rect.x = value.min(width - 1);
+                rect.width = rect.width.min(width - rect.x);
+            }
+            CropField::Y => {
+                rect.y = value.min(height - 1);
+                rect.height = rect.height.min(height - rect.y);
+            }
+            CropField::Width => rect.width = value.clamp(1, width - rect.x),
+            CropField::Height => rect.height = value.clamp(1, height - rect.y),
+        }
+
+        self.rect = Some(self.fit_aspect_ratio(rect, *field));
+    }
+
+    /// The size of [Self::apply]'s result for a frame of the given size.
+    pub fn cropped_size(&self, frame_size: (u32, u32)) -> Option<(u32, u32)> {
+        let rect = self.rect?.scale(self.image_size, frame_size)?;
+
+        Some((rect.width, rect.height))
+    }
+
+    /// The selected part of `frame`, which might be smaller than the image itself,
+    /// e.g. for rasterized vector images.
+    pub fn apply(&self, frame: &RgbaImage) -> Option<RgbaImage> {
+        let rect = self.rect?.scale(self.image_size, frame.dimensions())?;
+
+        Some(image::imageops::crop_imm(frame, rect.x, rect.y, rect.width, rect.height).to_image())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> CropRect {
+        CropRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn dragged(
+        image_size: (u32, u32),
+        aspect_ratio: AspectRatio,
+        from: (u32, u32),
+        to: (u32, u32),
+    ) -> Option<CropRect> {
+        let mut crop = Crop::new(image_size);
+        crop.aspect_ratio = aspect_ratio;
+        crop.start_drag(from);
+        crop.drag(to);
+
+        crop.rect
+    }
+
+    #[test]
+    fn drag_with_presets() {
+        let free = dragged((100, 100), AspectRatio::Free, (10, 10), (50, 30));
+        assert_eq!(free, Some(rect(10, 10, 40, 20)));
+
+        let square = dragged((100, 100), AspectRatio::Square, (10, 10), (50, 30));
+        assert_eq!(square, Some(rect(10, 10, 40, 40)));
+
+        let wide = dragged((200, 200), AspectRatio::SixteenNine, (0, 0), (160, 10));
+        assert_eq!(wide, Some(rect(0, 0, 160, 90)));
+
+        let original = dragged((200, 100), AspectRatio::Original, (0, 0), (60, 10));
+        assert_eq!(original, Some(rect(0, 0, 60, 30)));
+    }
+
+    #[test]
+    fn reverse_drag() {
+        let free = dragged((100, 100), AspectRatio::Free, (50, 40), (10, 20));
+        assert_eq!(free, Some(rect(10, 20, 40, 20)));
+
+        // limited by the room left of the start
+        let square = dragged((100, 100), AspectRatio::Square, (20, 30), (0, 0));
+        assert_eq!(square, Some(rect(0, 10, 20, 20)));
+    }
+
+    #[test]
+    fn empty_drag() {
+        assert_eq!(
+            dragged((100, 100), AspectRatio::Free, (10, 10), (10, 50)),
+            None
+        );
+    }
+
+    #[test]
+    fn fit_aspect_ratio_within_image() {
+        let mut crop = Crop::new((100, 100));
+        crop.aspect_ratio = AspectRatio::Square;
+
+        assert_eq!(
+            crop.fit_aspect_ratio(rect(80, 0, 20, 10), CropField::Width),
+            rect(80, 0, 20, 20)
+        );
+        // the width is limited by the right edge of the image
+        assert_eq!(
+            crop.fit_aspect_ratio(rect(80, 0, 20, 50), CropField::Height),
+            rect(80, 0, 20, 20)
+        );
+    }
+
+    #[test]
+    fn cycle_aspect_ratio_shrinks_rect() {
+        let mut crop = Crop::new((200, 100));
+        crop.rect = Some(rect(0, 0, 100, 80));
+
+        crop.cycle_aspect_ratio();
+        assert_eq!(crop.aspect_ratio, AspectRatio::Original);
+        assert_eq!(crop.rect, Some(rect(0, 0, 100, 50)));
+    }
+
+    #[test]
+    fn typed_values() {
+        let mut crop = Crop::new((100, 80));
+        assert!(crop.type_key("tab"));
+        assert_eq!(crop.rect, Some(rect(0, 0, 100, 80)));
+
+        for key in ["1", "5", "0"] {
+            crop.type_key(key);
+        }
+        assert_eq!(crop.rect, Some(rect(99, 0, 1, 80)));
+
+        crop.type_key("backspace");
+        crop.type_key("backspace");
+        assert_eq!(crop.rect, Some(rect(1, 0, 1, 80)));
+
+        crop.aspect_ratio = AspectRatio::Square;
+        crop.rect = Some(rect(0, 0, 100, 80));
+        crop.type_key("tab");
+        crop.type_key("tab");
+        for key in ["5", "0"] {
+            crop.type_key(key);
+        }
+        assert_eq!(crop.editing, Some((CropField::Width, "50".to_string())));
+        assert_eq!(crop.rect, Some(rect(0, 0, 50, 50)));
+
+        assert!(!crop.type_key("a"));
+    }
+
+    #[test]
+    fn scale_to_frame() {
+        // vector images rasterized at twice their size
+        assert_eq!(
+            rect(10, 20, 30, 40).scale((100, 100), (200, 200)),
+            Some(rect(20, 40, 60, 80))
+        );
+        // RAW previews that are smaller than the sensor resolution
+        assert_eq!(
+            rect(5999, 3999, 1, 1).scale((6000, 4000), (1500, 1000)),
+            Some(rect(1499, 999, 1, 1))
+        );
+        assert_eq!(rect(0, 0, 10, 10).scale((100, 100), (0, 0)), None);
+    }
+
+    #[test]
+    fn apply_to_empty_frame() {
+        let mut crop = Crop::new((100, 100));
+        crop.rect = Some(rect(0, 0, 10, 10));
+
+        assert_eq!(crop.cropped_size((0, 0)), None);
+        assert!(crop.apply(&RgbaImage::new(0, 0)).is_none());
+        assert_eq!(crop.cropped_size((50, 50)), Some((5, 5)));
+    }
+}
@@ -0,0 +1,715 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Cursor, Read, Seek},
+    path::Path,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail};
+
+use gpui::{App, Asset, ImageCacheError, RenderImage};
+use image::{
+    AnimationDecoder, DynamicImage, Frame, Frames, GrayAlphaImage, GrayImage, ImageBuffer,
+    ImageDecoder, ImageFormat, ImageReader, ImageResult, RgbImage, Rgba, RgbaImage,
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+};
+use resvg::usvg;
+use tiff::{
+    ColorType as TiffColorType,
+    decoder::{Decoder as TiffDecoder, DecodingResult},
+    tags::Tag as TiffTag,
+};
+
+use crate::{
+    archive,
+    color::{self, ColorProfile, PngColorChunks},
+    config::CONFIG,
+    format::OptionalFormat,
+    image_entry::ImageEntry,
+};
+
+/// Frames with a shorter delay are shown for [DEFAULT_FRAME_DELAY] instead,
+/// which is what browsers do as well.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// The TIFF tag of an embedded ICC profile, which the tiff crate has no name for.
+const TIFF_ICC_PROFILE_TAG: u16 = 34675;
+
+#[cfg(feature = "pdf")]
+const PDF_RENDER_DPI: f32 = 150.0;
+#[cfg(feature = "pdf")]
+const POINTS_PER_INCH: f32 = 72.0;
+
+/// A single frame of a [DecodedImage].
+pub struct DecodedFrame {
+    /// The frame, ready to be rendered by gpui.
+    ///
+    /// This is the only copy of the pixels that is kept, see [to_rgba] for working with them.
+    pub render_image: Arc<RenderImage>,
+    /// How long the frame should be shown before advancing to the next one.
+    pub delay: Duration,
+}
+
+/// Options for [decode].
+#[derive(Clone, Copy, Debug)]
+pub struct DecodeOptions {
+    /// The page to decode, only relevant for multi-page documents.
+    pub page: usize,
+    /// Vector images are rasterized at their intrinsic size, but at most this many pixels.
+    pub max_svg_raster_size: u32,
+    /// Whether to convert the image from its color profile to the display profile.
+    pub color_management: bool,
+    /// Whether to skip all but the first frame of animations, e.g. for previews.
+    pub first_frame_only: bool,
+}
+
+/// The first frame of an image, loaded via gpui's asset cache.
+///
+/// Used where many images are shown at once and playback isn't needed, e.g. when scrolling
+/// through all images. The source is the image and whether color management is enabled.
+pub enum PreviewImage {}
+
+impl Asset for PreviewImage {
+    type Source = (ImageEntry, bool);
+    type Output = Result<Arc<RenderImage>, ImageCacheError>;
+
+    fn load(
+        (source, color_management): Self::Source,
+        _cx: &mut App,
+    ) -> impl Future<Output = Self::Output> + Send + 'static {
+        let options = DecodeOptions {
+            page: 0,
+            max_svg_raster_size: CONFIG.get().unwrap().max_svg_raster_size,
+            color_management,
+            first_frame_only: true,
+        };
+
+        async move {
+            let decoded =
+                decode(&source, options).map_err(|err| ImageCacheError::Other(Arc::new(err)))?;
+
+            decoded
+                .frames
+                .into_iter()
+                .next()
+                .map(|frame| frame.render_image)
+                .ok_or_else(|| ImageCacheError::Other(Arc::new(anyhow!("image has no frames"))))
+        }
+    }
+}
+
+impl PreviewImage {
+    /// Remove the preview of `source` from gpui's asset cache, which never evicts assets by
+    /// itself, and free its texture.
+    pub fn evict(source: &(ImageEntry, bool), cx: &mut App) {
+        let (preview, _) = cx.fetch_asset::<Self>(source);
+        if let Some(Ok(image)) = preview.peek() {
+            cx.drop_image(image.clone(), None);
+        }
+
+        cx.remove_asset::<Self>(source);
+    }
+}
+
+/// A decoded image, consisting of multiple frames if the image is animated.
+pub struct DecodedImage {
+    pub frames: Vec<DecodedFrame>,
+    /// Number of pages of the document, the frames belong to the decoded page.
+    pub page_count: usize,
+    /// The parsed SVG if this is a vector image, used for re-rasterizing it at different sizes.
+    pub svg: Option<Arc<usvg::Tree>>,
+    /// Name of the embedded color profile, if the image isn't plain sRGB.
+    pub color_profile: Option<String>,
+    /// Why the color profile couldn't be applied, in which case the frames are unconverted.
+    pub color_error: Option<String>,
+}
+
+impl DecodedImage {
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// The total duration of one loop of the animation.
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delay).sum()
+    }
+
+    /// The intrinsic size of the SVG in pixels, i.e. its `width` and `height` attributes,
+    /// falling back to the size of its viewBox if they're missing.
+    pub fn svg_size(&self) -> Option<(f32, f32)> {
+        self.svg.as_ref().map(|tree| {
+            let size = tree.size();
+            (size.width(), size.height())
+        })
+    }
+
+    /// Replace the rendered frame of the SVG, returning the previous one.
+    pub fn replace_svg_raster(&mut self, buffer: RgbaImage) -> Option<DecodedFrame> {
+        let frame = DecodedFrame::new(buffer, Duration::ZERO);
+
+        if self.frames.is_empty() {
+            self.frames.push(frame);
+            None
+        } else {
+            Some(std::mem::replace(&mut self.frames[0], frame))
+        }
+    }
+}
+
+/// The RGBA frames of a decoded image, before they are converted to the display profile
+/// and for rendering, which is done by [Self::into_decoded].
+struct DecodedBuffers {
+    frames: Vec<(RgbaImage, Duration)>,
+    page_count: usize,
+    svg: Option<Arc<usvg::Tree>>,
+}
+
+impl DecodedBuffers {
+    fn from_frames(frames: Vec<Frame>) -> Self {
+        let frames = frames
+            .into_iter()
+            .map(|frame| {
+                let delay = Duration::from(frame.delay());
+                let delay = if delay < MIN_FRAME_DELAY {
+                    DEFAULT_FRAME_DELAY
+                } else {
+                    delay
+                };
+
+                (frame.into_buffer(), delay)
+            })
+            .collect();
+
+        Self {
+            frames,
+            page_count: 1,
+            svg: None,
+        }
+    }
+
+    fn from_image(image: &DynamicImage) -> Self {
+        Self {
+            frames: vec![(image.to_rgba8(), Duration::ZERO)],
+            page_count: 1,
+            svg: None,
+        }
+    }
+
+    fn from_page(image: &DynamicImage, page_count: usize) -> Self {
+        Self {
+            page_count,
+            ..Self::from_image(image)
+        }
+    }
+
+    /// Convert the frames from the color profile to the display profile if `enabled`, and
+    /// then for rendering.
+    fn into_decoded(mut self, profile: Option<ColorProfile>, enabled: bool) -> DecodedImage {
+        let color_profile = profile.as_ref().map(ColorProfile::name);
+        let color_error = profile
+            .filter(|_| enabled)
+            .and_then(|profile| {
+                color::convert_to_display(
+                    self.frames.iter_mut().map(|(buffer, _)| buffer),
+                    &profile,
+                )
+                .err()
+            })
+            .map(|err| err.to_string());
+
+        DecodedImage {
+            frames: self
+                .frames
+                .into_iter()
+                .map(|(buffer, delay)| DecodedFrame::new(buffer, delay))
+                .collect(),
+            page_count: self.page_count,
+            svg: self.svg,
+            color_profile,
+            color_error,
+        }
+    }
+}
+
+impl DecodedFrame {
+    fn new(buffer: RgbaImage, delay: Duration) -> Self {
+        Self {
+            render_image: into_render_image(buffer),
+            delay,
+        }
+    }
+
+    /// The width and height of the frame in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        render_image_size(&self.render_image)
+    }
+
+    /// The color of the pixel, if it's within the frame.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Rgba<u8>> {
+        let (width, height) = self.size();
+        if x >= width || y >= height {
+            return None;
+        }
+
+        let index = (y as usize * width as usize + x as usize) * 4;
+        let [b, g, r, a] = self
+            .render_image
+            .as_bytes(0)?
+            .get(index..index + 4)?
+            .try_into()
+            .ok()?;
+
+        Some(Rgba([r, g, b, a]))
+    }
+}
+
+/// Convert the RGBA pixel data to a [RenderImage], which expects BGRA pixel data.
+pub fn into_render_image(mut buffer: RgbaImage) -> Arc<RenderImage> {
+    for pixel in buffer.pixels_mut() {
+        pixel.0.swap(0, 2);
+    }
+
+    Arc::new(RenderImage::new(vec![Frame::new(buffer)]))
+}
+
+fn render_image_size(image: &RenderImage) -> (u32, u32) {
+    let size = image.size(0);
+
+    (
+        u32::try_from(size.width.0).unwrap_or_default(),
+        u32::try_from(size.height.0).unwrap_or_default(),
+    )
+}
+
+/// The RGBA pixel data of the [RenderImage], e.g. for computing metrics or saving a frame.
+///
+/// This copies all pixels, so it should be run in the background for large images.
+pub fn to_rgba(image: &RenderImage) -> RgbaImage {
+    let (width, height) = render_image_size(image);
+    let mut data = image.as_bytes(0).unwrap_or_default().to_vec();
+    for pixel in data.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+
+    RgbaImage::from_raw(width, height, data).unwrap_or_default()
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"))
+}
+
+/// The system fonts, used for rendering text in SVGs.
+///
+/// Loading them is rather slow, hence they're only loaded once.
+fn system_fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = usvg::fontdb::Database::new();
+            fonts.load_system_fonts();
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
+/// Limit the width so that neither side of the rasterized SVG exceeds `max_size` pixels.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn clamp_svg_raster_width(tree: &usvg::Tree, width: u32, max_size: u32) -> u32 {
+    let size = tree.size();
+    let max_width = if size.height() > size.width() {
+        (max_size as f32 * size.width() / size.height()) as u32
+    } else {
+        max_size
+    };
+
+    width.clamp(1, max_width.max(1))
+}
+
+/// Rasterize the SVG so that the resulting image is `width` pixels wide.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn rasterize_svg(tree: &usvg::Tree, width: u32) -> anyhow::Result<RgbaImage> {
+    let size = tree.size();
+    let scale = width as f32 / size.width();
+    let height = ((size.height() * scale).ceil() as u32).max(1);
+
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| anyhow!("invalid SVG size {width}x{height}"))?;
+    resvg::render(
+        tree,
+        resvg::tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    // tiny-skia uses premultiplied alpha
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    RgbaImage::from_raw(width, height, data).ok_or_else(|| anyhow!("invalid SVG pixel data"))
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn decode_svg(
+    data: &[u8],
+    resources_dir: Option<&Path>,
+    max_size: u32,
+) -> anyhow::Result<DecodedBuffers> {
+    let options = usvg::Options {
+        fontdb: system_fonts(),
+        resources_dir: resources_dir.map(Path::to_path_buf),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_data(data, &options)?;
+
+    // start with the intrinsic size, the image is re-rasterized once its displayed size is known
+    let width = clamp_svg_raster_width(&tree, tree.size().width().ceil() as u32, max_size);
+    let buffer = rasterize_svg(&tree, width)?;
+
+    Ok(DecodedBuffers {
+        frames: vec![(buffer, Duration::ZERO)],
+        page_count: 1,
+        svg: Some(Arc::new(tree)),
+    })
+}
+
+#[cfg(feature = "heic")]
+fn decode_heic(data: &[u8]) -> anyhow::Result<(DynamicImage, Option<ColorProfile>)> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let context = HeifContext::read_from_bytes(data)?;
+    let handle = context.primary_image_handle()?;
+    let color_profile = handle
+        .color_profile_raw()
+        .map(|profile| ColorProfile::Icc(profile.data));
+    let image = LibHeif::new().decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)?;
+
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or_else(|| anyhow!("HEIC image has no interleaved RGBA data"))?;
+    let row_length = plane.width as usize * 4;
+    let data = plane
+        .data
+        .chunks(plane.stride)
+        .take(plane.height as usize)
+        .flat_map(|row| &row[..row_length])
+        .copied()
+        .collect();
+
+    let image = RgbaImage::from_raw(plane.width, plane.height, data)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| anyhow!("invalid HEIC pixel data"))?;
+
+    Ok((image, color_profile))
+}
+
+#[cfg(feature = "jxl")]
+fn decode_jxl(reader: impl Read) -> anyhow::Result<(DynamicImage, Option<ColorProfile>)> {
+    let mut decoder = jxl_oxide::integration::JxlDecoder::new(reader)?;
+    let color_profile = icc_color_profile(&mut decoder);
+
+    Ok((DynamicImage::from_decoder(decoder)?, color_profile))
+}
+
+/// Convert the current page of the TIFF decoder to an image.
+fn tiff_page_to_image<R: Read + Seek>(
+    decoder: &mut TiffDecoder<R>,
+) -> anyhow::Result<DynamicImage> {
+    let (width, height) = decoder.dimensions()?;
+
+    let image = match (decoder.colortype()?, decoder.read_image()?) {
+        (TiffColorType::Gray(1), DecodingResult::U8(data)) => {
+            // bilevel images (e.g. scanned documents) store 8 pixels per byte
+            let row_length = width.div_ceil(8) as usize;
+            let pixels = data
+                .chunks(row_length)
+                .take(height as usize)
+                .flat_map(|row| {
+                    (0..width as usize).map(move |x| {
+                        if row[x / 8] & (0x80 >> (x % 8)) == 0 {
+                            0
+                        } else {
+                            255
+                        }
+                    })
+                })
+                .collect();
+            GrayImage::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8)
+        }
+        (TiffColorType::Gray(8), DecodingResult::U8(data)) => {
+            GrayImage::from_raw(width, height, data).map(DynamicImage::ImageLuma8)
+        }
+        (TiffColorType::GrayA(8), DecodingResult::U8(data)) => {
+            GrayAlphaImage::from_raw(width, height, data).map(DynamicImage::ImageLumaA8)
+        }
+        (TiffColorType::RGB(8), DecodingResult::U8(data)) => {
+            RgbImage::from_raw(width, height, data).map(DynamicImage::ImageRgb8)
+        }
+        (TiffColorType::RGBA(8), DecodingResult::U8(data)) => {
+            RgbaImage::from_raw(width, height, data).map(DynamicImage::ImageRgba8)
+        }
+        (TiffColorType::Gray(16), DecodingResult::U16(data)) => {
+            ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLuma16)
+        }
+        (TiffColorType::RGB(16), DecodingResult::U16(data)) => {
+            ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgb16)
+        }
+        (TiffColorType::RGBA(16), DecodingResult::U16(data)) => {
+            ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba16)
+        }
+        (color_type, _) => bail!("unsupported TIFF color type {color_type:?}"),
+    };
+
+    image.ok_or_else(|| anyhow!("invalid TIFF pixel data"))
+}
+
+/// Decode the frames of the animation, or only the first one if `first_frame_only`.
+fn collect_frames(frames: Frames<'_>, first_frame_only: bool) -> ImageResult<Vec<Frame>> {
+    let count = if first_frame_only { 1 } else { usize::MAX };
+
+    frames.take(count).collect()
+}
+
+fn icc_color_profile(decoder: &mut impl ImageDecoder) -> Option<ColorProfile> {
+    decoder.icc_profile().ok().flatten().map(ColorProfile::Icc)
+}
+
+/// Decode the page of the TIFF, also returning its color profile.
+fn decode_tiff<R: BufRead + Seek>(
+    mut reader: R,
+    page: usize,
+) -> anyhow::Result<(DecodedBuffers, Option<ColorProfile>)> {
+    // the indices of the IFDs that are pages, i.e. not thumbnails of another page
+    let mut pages = Vec::new();
+    let mut decoder = TiffDecoder::new(&mut reader)?;
+    for index in 0.. {
+        let subfile_type: Option<u32> = decoder
+            .find_tag_unsigned(TiffTag::NewSubfileType)
+            .ok()
+            .flatten();
+        if subfile_type.is_none_or(|subfile_type| subfile_type & 1 == 0) {
+            pages.push(index);
+        }
+
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()?;
+    }
+    drop(decoder);
+    reader.rewind()?;
+
+    // the image crate supports more TIFF variants, so prefer it for single page images
+    if matches!(pages.as_slice(), [] | [0]) {
+        let (image, color_profile) = decode_tiff_first_image(reader)?;
+        return Ok((DecodedBuffers::from_image(&image), color_profile));
+    }
+
+    let index = pages[page.min(pages.len() - 1)];
+    let mut decoder = TiffDecoder::new(&mut reader)?;
+    decoder.seek_to_image(index)?;
+    let color_profile = decoder
+        .get_tag_u8_vec(TiffTag::Unknown(TIFF_ICC_PROFILE_TAG))
+        .ok()
+        .map(ColorProfile::Icc);
+    let (image, color_profile) = match tiff_page_to_image(&mut decoder) {
+        Ok(image) => (image, color_profile),
+        // e.g. palette or CMYK images, which the image crate can only decode on the first page
+        Err(_) if index == 0 => {
+            drop(decoder);
+            reader.rewind()?;
+            decode_tiff_first_image(reader)?
+        }
+        Err(err) => return Err(err),
+    };
+
+    Ok((
+        DecodedBuffers::from_page(&image, pages.len()),
+        color_profile,
+    ))
+}
+
+/// Decode the first image of the TIFF with the image crate.
+fn decode_tiff_first_image<R: BufRead + Seek>(
+    reader: R,
+) -> anyhow::Result<(DynamicImage, Option<ColorProfile>)> {
+    let mut decoder = ImageReader::with_format(reader, ImageFormat::Tiff).into_decoder()?;
+    let color_profile = icc_color_profile(&mut decoder);
+
+    Ok((DynamicImage::from_decoder(decoder)?, color_profile))
+}
+
+#[cfg(feature = "pdf")]
+/// Render the page of the PDF, which pdfium does in sRGB.
+fn decode_pdf(data: Vec<u8>, page: usize) -> anyhow::Result<DecodedBuffers> {
+    use pdfium_render::prelude::*;
+
+    let pdfium = Pdfium::new(Pdfium::bind_to_system_library()?);
+    let document = pdfium.load_pdf_from_byte_vec(data, None)?;
+    let pages = document.pages();
+
+    let page_count = usize::from(pages.len());
+    let page = pages.get(u16::try_from(page.min(page_count.saturating_sub(1)))?)?;
+    let image = page
+        .render_with_config(
+            &PdfRenderConfig::new().scale_page_by_factor(PDF_RENDER_DPI / POINTS_PER_INCH),
+        )?
+        .as_image();
+
+    Ok(DecodedBuffers::from_page(&image, page_count))
+}
+
+/// Decode all frames of the image.
+///
+/// This is potentially slow, so it should be run in the background.
+pub fn decode(entry: &ImageEntry, options: DecodeOptions) -> anyhow::Result<DecodedImage> {
+    let (decoded, color_profile) = match entry {
+        ImageEntry::File(path) => decode_file(path, options),
+        ImageEntry::ArchiveEntry { archive, name } => {
+            let data = archive::read_entry(archive, name)?;
+            decode_reader(Cursor::new(data), Path::new(name), options)
+        }
+        ImageEntry::Pasted(image) => decode_reader(
+            Cursor::new(image.data.clone()),
+            Path::new(&image.file_name()),
+            options,
+        ),
+    }?;
+
+    Ok(decoded.into_decoded(color_profile, options.color_management))
+}
+
+/// Decode the file, also returning its color profile.
+fn decode_file(
+    path: &Path,
+    options: DecodeOptions,
+) -> anyhow::Result<(DecodedBuffers, Option<ColorProfile>)> {
+    #[cfg(feature = "raw")]
+    if crate::raw::is_raw(path) {
+        let (image, icc_profile) = crate::raw::decode_preview(path)?;
+        return Ok((
+            DecodedBuffers::from_image(&image),
+            icc_profile.map(ColorProfile::Icc),
+        ));
+    }
+
+    decode_reader(BufReader::new(File::open(path)?), path, options)
+}
+
+/// Decode the image from the reader, also returning its color profile. `path` is only used
+/// for detecting vector images and resolving the resources they reference.
+fn decode_reader<R: BufRead + Seek>(
+    mut reader: R,
+    path: &Path,
+    options: DecodeOptions,
+) -> anyhow::Result<(DecodedBuffers, Option<ColorProfile>)> {
+    if is_svg(path) {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let decoded = decode_svg(&data, path.parent(), options.max_svg_raster_size)?;
+        return Ok((decoded, None));
+    }
+
+    if let Some(format) = OptionalFormat::detect_reader(&mut reader)? {
+        if !format.is_supported() {
+            bail!("support for {format} images is not enabled");
+        }
+
+        match format {
+            #[cfg(feature = "heic")]
+            OptionalFormat::Heic => {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+
+                let (image, color_profile) = decode_heic(&data)?;
+                return Ok((DecodedBuffers::from_image(&image), color_profile));
+            }
+            #[cfg(feature = "jxl")]
+            OptionalFormat::Jxl => {
+                let (image, color_profile) = decode_jxl(reader)?;
+                return Ok((DecodedBuffers::from_image(&image), color_profile));
+            }
+            #[cfg(feature = "pdf")]
+            OptionalFormat::Pdf => {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+
+                return Ok((decode_pdf(data, options.page)?, None));
+            }
+            // AVIF and QOI are supported by the image crate
+            _ => {}
+        }
+    }
+
+    let reader = ImageReader::new(reader).with_guessed_format()?;
+
+    Ok(match reader.format() {
+        Some(ImageFormat::Gif) => {
+            let decoder = GifDecoder::new(reader.into_inner())?;
+            (
+                DecodedBuffers::from_frames(collect_frames(
+                    decoder.into_frames(),
+                    options.first_frame_only,
+                )?),
+                None,
+            )
+        }
+        Some(ImageFormat::Png) => {
+            let mut reader = reader.into_inner();
+            let color_chunks = PngColorChunks::read(&mut reader).unwrap_or_default();
+            reader.rewind()?;
+
+            let mut decoder = PngDecoder::new(reader)?;
+            let color_profile = color_chunks.profile(decoder.icc_profile().ok().flatten());
+            let decoded = if decoder.is_apng()? {
+                DecodedBuffers::from_frames(collect_frames(
+                    decoder.apng()?.into_frames(),
+                    options.first_frame_only,
+                )?)
+            } else {
+                DecodedBuffers::from_image(&DynamicImage::from_decoder(decoder)?)
+            };
+            (decoded, color_profile)
+        }
+        Some(ImageFormat::WebP) => {
+            let mut decoder = WebPDecoder::new(reader.into_inner())?;
+            let color_profile = icc_color_profile(&mut decoder);
+            let decoded = if decoder.has_animation() {
+                DecodedBuffers::from_frames(collect_frames(
+                    decoder.into_frames(),
+                    options.first_frame_only,
+                )?)
+            } else {
+                DecodedBuffers::from_image(&DynamicImage::from_decoder(decoder)?)
+            };
+            (decoded, color_profile)
+        }
+        Some(ImageFormat::Tiff) => decode_tiff(reader.into_inner(), options.page)?,
+        _ => {
+            let mut decoder = reader.into_decoder()?;
+            let color_profile = icc_color_profile(&mut decoder);
+            (
+                DecodedBuffers::from_image(&DynamicImage::from_decoder(decoder)?),
+                color_profile,
+            )
+        }
+    })
+}
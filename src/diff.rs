@@ -0,0 +1,255 @@
+//! Metrics and visualizations of the differences between two images of the same size,
+//! e.g. to compare render outputs or compression variants.
+
+use std::path::Path;
+
+use anyhow::bail;
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    config::CONFIG,
+    decoder::{self, DecodeOptions},
+    image_entry::ImageEntry,
+};
+
+/// The width and height of the windows the SSIM is computed for.
+const SSIM_WINDOW_SIZE: u32 = 8;
+/// The distance between neighbouring SSIM windows, which hence overlap.
+const SSIM_WINDOW_STEP: u32 = 4;
+/// Constants stabilizing the SSIM division, see the original paper by Wang et al.
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// How much two images differ.
+#[derive(Clone, Copy, Debug)]
+pub struct DiffMetrics {
+    /// Peak signal-to-noise ratio in dB over all channels, infinite for identical images.
+    pub psnr: f64,
+    /// Mean structural similarity of the luma, 1 for identical images.
+    pub ssim: f64,
+    /// The largest difference of a single channel of a single pixel.
+    pub max_delta: u8,
+}
+
+impl DiffMetrics {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn compute(a: &RgbaImage, b: &RgbaImage) -> anyhow::Result<Self> {
+        check_dimensions(a, b)?;
+
+        let mut squared_error = 0_u64;
+        let mut max_delta = 0;
+        for (a, b) in a.as_raw().iter().zip(b.as_raw()) {
+            let delta = a.abs_diff(*b);
+            squared_error += u64::from(delta) * u64::from(delta);
+            max_delta = max_delta.max(delta);
+        }
+
+        let psnr = if squared_error == 0 {
+            f64::INFINITY
+        } else {
+            let mean_squared_error = squared_error as f64 / a.as_raw().len() as f64;
+            10.0 * (255.0 * 255.0 / mean_squared_error).log10()
+        };
+
+        Ok(Self {
+            psnr,
+            ssim: ssim(a, b),
+            max_delta,
+        })
+    }
+}
+
+fn check_dimensions(a: &RgbaImage, b: &RgbaImage) -> anyhow::Result<()> {
+    if a.dimensions() != b.dimensions() {
+        bail!(
+            "the images differ in size: {}x{} and {}x{}",
+            a.width(),
+            a.height(),
+            b.width(),
+            b.height()
+        );
+    }
+    if a.width() == 0 || a.height() == 0 {
+        bail!("the images are empty");
+    }
+
+    Ok(())
+}
+
+/// The luma of each pixel, composited onto black.
+fn luma(image: &RgbaImage) -> Vec<f64> {
+    image
+        .pixels()
+        .map(|Rgba([r, g, b, a])| {
+            (0.299 * f64::from(*r) + 0.587 * f64::from(*g) + 0.114 * f64::from(*b)) * f64::from(*a)
+                / 255.0
+        })
+        .collect()
+}
+
+/// The mean SSIM of overlapping windows of the luma of both images.
+#[allow(clippy::similar_names)]
+fn ssim(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let (width, height) = a.dimensions();
+    let (luma_a, luma_b) = (luma(a), luma(b));
+    // images smaller than a window are compared as a whole
+    let (window_width, window_height) = (SSIM_WINDOW_SIZE.min(width), SSIM_WINDOW_SIZE.min(height));
+    let pixel_count = f64::from(window_width * window_height);
+
+    let mut sum = 0.0;
+    let mut window_count = 0_u32;
+    for y in window_starts(height, window_height) {
+        for x in window_starts(width, window_width) {
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) =
+                (0.0, 0.0, 0.0, 0.0, 0.0);
+            for window_y in y..y + window_height {
+                let row = (window_y * width) as usize;
+                for index in row + x as usize..row + (x + window_width) as usize {
+                    let (a, b) = (luma_a[index], luma_b[index]);
+                    sum_a += a;
+                    sum_b += b;
+                    sum_aa += a * a;
+                    sum_bb += b * b;
+                    sum_ab += a * b;
+                }
+            }
+
+            let (mean_a, mean_b) = (sum_a / pixel_count, sum_b / pixel_count);
+            let variance_a = sum_aa / pixel_count - mean_a * mean_a;
+            let variance_b = sum_bb / pixel_count - mean_b * mean_b;
+            let covariance = sum_ab / pixel_count - mean_a * mean_b;
+
+            sum += ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1)
+                    * (variance_a + variance_b + SSIM_C2));
+            window_count += 1;
+        }
+    }
+
+    sum / f64::from(window_count)
+}
+
+/// Where the SSIM windows along an axis start, including one aligned to the end of the axis
+/// such that the last pixels are covered even if the step doesn't line up with them.
+fn window_starts(size: u32, window_size: u32) -> impl Iterator<Item = u32> {
+    let last = size - window_size;
+    let last_is_aligned = last.is_multiple_of(SSIM_WINDOW_STEP);
+
+    (0..=last)
+        .step_by(SSIM_WINDOW_STEP as usize)
+        .chain((!last_is_aligned).then_some(last))
+}
+
+/// The absolute difference of each channel, multiplied by `amplification` to make small
+/// differences visible. Differences in transparency are added to all color channels.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn difference_image(
+    a: &RgbaImage,
+    b: &RgbaImage,
+    amplification: f32,
+) -> anyhow::Result<RgbaImage> {
+    check_dimensions(a, b)?;
+
+    Ok(RgbaImage::from_fn(a.width(), a.height(), |x, y| {
+        let (a, b) = (a.get_pixel(x, y), b.get_pixel(x, y));
+        let alpha_delta = a[3].abs_diff(b[3]);
+        let channel = |index: usize| {
+            let delta = a[index].abs_diff(b[index]).saturating_add(alpha_delta);
+            (f32::from(delta) * amplification).min(255.0) as u8
+        };
+
+        Rgba([channel(0), channel(1), channel(2), 255])
+    }))
+}
+
+/// Decode the first frame of both files and compute their [DiffMetrics].
+///
+/// The pixels are compared as stored in the files, i.e. without color management.
+pub fn diff_files(a: &Path, b: &Path) -> anyhow::Result<DiffMetrics> {
+    let options = DecodeOptions {
+        page: 0,
+        max_svg_raster_size: CONFIG.get().unwrap().max_svg_raster_size,
+        color_management: false,
+        first_frame_only: true,
+    };
+    let decode = |path: &Path| -> anyhow::Result<RgbaImage> {
+        let decoded = decoder::decode(&ImageEntry::File(path.to_path_buf()), options)?;
+        match decoded.frames.into_iter().next() {
+            Some(frame) => Ok(decoder::to_rgba(&frame.render_image)),
+            None => bail!("{} contains no image", path.display()),
+        }
+    };
+
+    DiffMetrics::compute(&decode(a)?, &decode(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAY: Rgba<u8> = Rgba([100, 100, 100, 255]);
+
+    /// A gray image with the red channel of the pixel at (1, 2) increased by `delta`.
+    fn with_delta(width: u32, height: u32, delta: u8) -> RgbaImage {
+        let mut image = RgbaImage::from_pixel(width, height, GRAY);
+        image.get_pixel_mut(1, 2)[0] += delta;
+
+        image
+    }
+
+    #[test]
+    fn identical_images() {
+        let image = with_delta(16, 16, 0);
+        let metrics = DiffMetrics::compute(&image, &image).unwrap();
+
+        assert!(metrics.psnr.is_infinite());
+        assert!((metrics.ssim - 1.0).abs() < 1e-9);
+        assert_eq!(metrics.max_delta, 0);
+    }
+
+    #[test]
+    fn one_pixel_delta() {
+        let (a, b) = (with_delta(16, 16, 0), with_delta(16, 16, 10));
+        let metrics = DiffMetrics::compute(&a, &b).unwrap();
+
+        // one squared error of 100 among 16 * 16 * 4 channel values
+        let expected_psnr = 10.0 * (255.0 * 255.0 / (100.0 / 1024.0_f64)).log10();
+        assert!((metrics.psnr - expected_psnr).abs() < 1e-9);
+        assert!(metrics.ssim < 1.0 && metrics.ssim > 0.9);
+        assert_eq!(metrics.max_delta, 10);
+
+        let difference = difference_image(&a, &b, 2.0).unwrap();
+        assert_eq!(*difference.get_pixel(1, 2), Rgba([20, 0, 0, 255]));
+        assert_eq!(*difference.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn mismatched_sizes() {
+        let (a, b) = (with_delta(16, 16, 0), with_delta(16, 8, 0));
+
+        assert!(DiffMetrics::compute(&a, &b).is_err());
+        assert!(difference_image(&a, &b, 1.0).is_err());
+    }
+
+    #[test]
+    fn smaller_than_ssim_window() {
+        let (a, b) = (with_delta(4, 3, 0), with_delta(4, 3, 50));
+
+        let identical = DiffMetrics::compute(&a, &a).unwrap();
+        assert!((identical.ssim - 1.0).abs() < 1e-9);
+
+        let metrics = DiffMetrics::compute(&a, &b).unwrap();
+        assert!(metrics.ssim.is_finite() && metrics.ssim < 1.0);
+        assert_eq!(metrics.max_delta, 50);
+    }
+
+    #[test]
+    fn ssim_covers_edges() {
+        let a = with_delta(18, 18, 0);
+        let mut b = a.clone();
+        b.get_pixel_mut(17, 17)[0] += 50;
+
+        assert_eq!(window_starts(18, 8).collect::<Vec<_>>(), [0, 4, 8, 10]);
+        assert!(DiffMetrics::compute(&a, &b).unwrap().ssim < 1.0);
+    }
+}
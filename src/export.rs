@@ -0,0 +1,649 @@
+//! Saving the shown image in another format, optionally resized.
+
+use std::{
+    io::{Cursor, Write},
+    path::Path,
+};
+
+use anyhow::bail;
+use image::{
+    DynamicImage, ExtendedColorType, ImageDecoder, ImageEncoder, ImageReader, RgbaImage,
+    codecs::{
+        bmp::BmpEncoder, jpeg::JpegEncoder, png::PngEncoder, tiff::TiffEncoder, webp::WebPEncoder,
+    },
+    imageops::{self, FilterType},
+};
+
+use crate::{archive, config::CONFIG, image_entry::ImageEntry};
+
+/// The start of the APP1 segment containing the EXIF data of JPEGs.
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+// EXIF tags, see https://exiftool.org/TagNames/EXIF.html
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_PIXEL_X_DIMENSION: u16 = 0xA002;
+const TAG_PIXEL_Y_DIMENSION: u16 = 0xA003;
+
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+
+/// The formats images can be saved as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Png,
+    Jpeg,
+    /// Lossless WebP, as the image crate can't encode lossy WebP.
+    WebP,
+    Tiff,
+    Bmp,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 5] = [Self::Png, Self::Jpeg, Self::WebP, Self::Tiff, Self::Bmp];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::WebP => "WebP",
+            Self::Tiff => "TIFF",
+            Self::Bmp => "BMP",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::WebP => "webp",
+            Self::Tiff => "tiff",
+            Self::Bmp => "bmp",
+        }
+    }
+
+    /// The format matching the extension of `path`, if it can be saved as.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "webp" => Some(Self::WebP),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "bmp" => Some(Self::Bmp),
+            _ => None,
+        }
+    }
+}
+
+/// How the image is resized before saving it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resize {
+    Original,
+    /// Scale both sides to the percentage of their original size.
+    Percentage(u32),
+    /// Scale the image down such that neither side exceeds this many pixels.
+    MaxDimension(u32),
+}
+
+/// The filters available for resizing, from fastest to highest quality.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeFilter {
+    Nearest,
+    Bilinear,
+    Bicubic,
+    Gaussian,
+    Lanczos,
+}
+
+impl ResizeFilter {
+    pub const ALL: [Self; 5] = [
+        Self::Nearest,
+        Self::Bilinear,
+        Self::Bicubic,
+        Self::Gaussian,
+        Self::Lanczos,
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            Self::Nearest => t!("save_as.filter_nearest"),
+            Self::Bilinear => t!("save_as.filter_bilinear"),
+            Self::Bicubic => t!("save_as.filter_bicubic"),
+            Self::Gaussian => t!("save_as.filter_gaussian"),
+            Self::Lanczos => t!("save_as.filter_lanczos"),
+        }
+        .to_string()
+    }
+
+    fn filter_type(self) -> FilterType {
+        match self {
+            Self::Nearest => FilterType::Nearest,
+            Self::Bilinear => FilterType::Triangle,
+            Self::Bicubic => FilterType::CatmullRom,
+            Self::Gaussian => FilterType::Gaussian,
+            Self::Lanczos => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// How to save an image, chosen in the "save as" dialog.
+#[derive(Clone, Copy, Debug)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Quality of JPEGs between 1 and 100.
+    pub jpeg_quality: u8,
+    pub resize: Resize,
+    pub filter: ResizeFilter,
+    /// Whether to copy the EXIF data and the color profile of the original image. The
+    /// orientation and the dimensions in the EXIF data are updated for the saved pixels.
+    pub keep_metadata: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Png,
+            jpeg_quality: 90,
+            resize: Resize::Original,
+            filter: ResizeFilter::Lanczos,
+            keep_metadata: true,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// The size of the saved image, given the size of the shown image.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn output_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let scale = match self.resize {
+            Resize::Original => return (width, height),
+            Resize::Percentage(percentage) => percentage as f32 / 100.0,
+            // images are only scaled down, as scaling up doesn't add any detail
+            Resize::MaxDimension(max) => (max as f32 / width.max(height) as f32).min(1.0),
+        };
+
+        (
+            ((width as f32 * scale).round() as u32).max(1),
+            ((height as f32 * scale).round() as u32).max(1),
+        )
+    }
+}
+
+/// The metadata of the original image that is kept when saving it.
+#[derive(Default)]
+struct Metadata {
+    icc_profile: Option<Vec<u8>>,
+    exif: Option<Vec<u8>>,
+}
+
+impl Metadata {
+    fn read(image: &ImageEntry) -> anyhow::Result<Self> {
+        let data = match image {
+            ImageEntry::File(path) => std::fs::read(path)?,
+            ImageEntry::ArchiveEntry { archive, name } => archive::read_entry(archive, name)?,
+            ImageEntry::Pasted(image) => image.data.to_vec(),
+        };
+
+        Self::decode(data)
+    }
+
+    fn decode(data: Vec<u8>) -> anyhow::Result<Self> {
+        let mut decoder = ImageReader::new(Cursor::new(data))
+            .with_guessed_format()?
+            .into_decoder()?;
+
+        // some decoders include the header of the JPEG segment
+        let exif = decoder.exif_metadata()?.map(|exif| {
+            if exif.starts_with(EXIF_HEADER) {
+                exif[EXIF_HEADER.len()..].to_vec()
+            } else {
+                exif
+            }
+        });
+
+        Ok(Self {
+            icc_profile: decoder.icc_profile()?,
+            exif,
+        })
+    }
+}
+
+/// Save `buffer`, the shown frame of `image`, to `path`.
+///
+/// `color_managed` tells whether the pixels were converted to the display profile, which
+/// then replaces the color profile of the original image.
+pub fn export(
+    buffer: &RgbaImage,
+    image: Option<&ImageEntry>,
+    color_managed: bool,
+    options: &ExportOptions,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let (width, height) = options.output_size(buffer.dimensions());
+    let resized;
+    let buffer = if (width, height) == buffer.dimensions() {
+        buffer
+    } else {
+        resized = imageops::resize(buffer, width, height, options.filter.filter_type());
+        &resized
+    };
+
+    let mut metadata = Metadata::default();
+    if options.keep_metadata {
+        // images without metadata (or in formats without metadata support) are still saved
+        if let Some(image) = image {
+            metadata = Metadata::read(image).unwrap_or_default();
+        }
+        if color_managed {
+            metadata.icc_profile = match &CONFIG.get().unwrap().display_profile {
+                Some(profile) => Some(std::fs::read(profile)?),
+                None => None,
+            };
+        }
+        metadata.exif = metadata
+            .exif
+            .take()
+            .and_then(|mut exif| update_exif(&mut exif, (width, height)).map(|()| exif));
+    }
+
+    let mut data = Vec::new();
+    match options.format {
+        ExportFormat::Png => {
+            encode(PngEncoder::new(&mut data), buffer, &metadata)?;
+            if let Some(exif) = &metadata.exif {
+                insert_png_exif(&mut data, exif)?;
+            }
+        }
+        ExportFormat::Jpeg => {
+            // JPEGs have no alpha channel
+            let rgb = DynamicImage::ImageRgba8(buffer.clone()).into_rgb8();
+            let mut encoder = JpegEncoder::new_with_quality(&mut data, options.jpeg_quality);
+            if let Some(icc_profile) = metadata.icc_profile.clone() {
+                let _ = encoder.set_icc_profile(icc_profile);
+            }
+            encoder.write_image(rgb.as_raw(), width, height, ExtendedColorType::Rgb8)?;
+            if let Some(exif) = &metadata.exif {
+                insert_jpeg_exif(&mut data, exif)?;
+            }
+        }
+        ExportFormat::WebP => encode(WebPEncoder::new_lossless(&mut data), buffer, &metadata)?,
+        ExportFormat::Tiff => {
+            let mut cursor = Cursor::new(&mut data);
+            encode(TiffEncoder::new(&mut cursor), buffer, &metadata)?;
+        }
+        ExportFormat::Bmp => encode(BmpEncoder::new(&mut data), buffer, &metadata)?,
+    }
+
+    std::fs::File::create(path)?.write_all(&data)?;
+
+    Ok(())
+}
+
+fn encode(
+    mut encoder: impl ImageEncoder,
+    buffer: &RgbaImage,
+    metadata: &Metadata,
+) -> anyhow::Result<()> {
+    if let Some(icc_profile) = metadata.icc_profile.clone() {
+        // not all formats support color profiles
+        let _ = encoder.set_icc_profile(icc_profile);
+    }
+
+    encoder.write_image(
+        buffer.as_raw(),
+        buffer.width(),
+        buffer.height(),
+        ExtendedColorType::Rgba8,
+    )?;
+
+    Ok(())
+}
+
+/// Update the EXIF data to describe the saved pixels, which are saved as they are shown and
+/// might be cropped or resized: the orientation is reset and the dimensions are replaced.
+///
+/// Returns `None` if the EXIF data can't be parsed, in which case it should be dropped.
+fn update_exif(exif: &mut [u8], (width, height): (u32, u32)) -> Option<()> {
+    let mut exif = ExifWriter::new(exif)?;
+
+    for (tag, entry) in exif.entries(exif.ifd_offset(4)?)? {
+        match tag {
+            TAG_ORIENTATION => exif.set_integer(entry, 1)?,
+            TAG_EXIF_IFD => {
+                for (tag, sub_entry) in exif.entries(exif.ifd_offset(entry + 8)?)? {
+                    match tag {
+                        TAG_PIXEL_X_DIMENSION => exif.set_integer(sub_entry, width)?,
+                        TAG_PIXEL_Y_DIMENSION => exif.set_integer(sub_entry, height)?,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(())
+}
+
+/// Modifies values of EXIF data, which is structured like a TIFF file.
+struct ExifWriter<'a> {
+    data: &'a mut [u8],
+    little_endian: bool,
+}
+
+impl<'a> ExifWriter<'a> {
+    fn new(data: &'a mut [u8]) -> Option<Self> {
+        let little_endian = match data.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        let writer = Self {
+            data,
+            little_endian,
+        };
+
+        (writer.u16(2)? == 42).then_some(writer)
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?.try_into().ok()?;
+
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    /// The offset of an IFD, which is stored as LONG at `offset`.
+    fn ifd_offset(&self, offset: usize) -> Option<usize> {
+        let bytes = self.data.get(offset..offset + 4)?.try_into().ok()?;
+
+        usize::try_from(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+        .ok()
+    }
+
+    fn write(&mut self, offset: usize, bytes: &[u8]) -> Option<()> {
+        self.data
+            .get_mut(offset..offset + bytes.len())?
+            .copy_from_slice(bytes);
+
+        Some(())
+    }
+
+    /// The tags and offsets of the entries of the IFD at the offset.
+    fn entries(&self, offset: usize) -> Option<Vec<(u16, usize)>> {
+        (0..usize::from(self.u16(offset)?))
+            .map(|index| {
+                let entry = offset + 2 + index * 12;
+                Some((self.u16(entry)?, entry))
+            })
+            .collect()
+    }
+
+    /// Replace the value of the entry by a single SHORT, or a LONG if it doesn't fit.
+    fn set_integer(&mut self, entry: usize, value: u32) -> Option<()> {
+        // a single value always fits into the 4 bytes of the value field
+        let (kind, value) = match u16::try_from(value) {
+            Ok(short) if self.u16(entry + 2)? == TYPE_SHORT => {
+                let bytes = if self.little_endian {
+                    short.to_le_bytes()
+                } else {
+                    short.to_be_bytes()
+                };
+                (TYPE_SHORT, [bytes[0], bytes[1], 0, 0])
+            }
+            _ => (
+                TYPE_LONG,
+                if self.little_endian {
+                    value.to_le_bytes()
+                } else {
+                    value.to_be_bytes()
+                },
+            ),
+        };
+        let (kind, count) = if self.little_endian {
+            (kind.to_le_bytes(), 1_u32.to_le_bytes())
+        } else {
+            (kind.to_be_bytes(), 1_u32.to_be_bytes())
+        };
+
+        self.write(entry + 2, &kind)?;
+        self.write(entry + 4, &count)?;
+        self.write(entry + 8, &value)
+    }
+}
+
+/// Insert the EXIF data as APP1 segment after the start of image marker and the JFIF APP0
+/// segment, which has to be the first segment.
+fn insert_jpeg_exif(data: &mut Vec<u8>, exif: &[u8]) -> anyhow::Result<()> {
+    let Ok(length) = u16::try_from(2 + EXIF_HEADER.len() + exif.len()) else {
+        bail!("the EXIF data is too large for a JPEG");
+    };
+
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&length.to_be_bytes());
+    segment.extend_from_slice(EXIF_HEADER);
+    segment.extend_from_slice(exif);
+
+    let mut position = 2;
+    while data.get(position..position + 2) == Some([0xFF, 0xE0].as_slice()) {
+        let Some(&[high, low]) = data.get(position + 2..position + 4) else {
+            bail!("the JPEG is truncated");
+        };
+        position += 2 + usize::from(u16::from_be_bytes([high, low]));
+    }
+    if position > data.len() {
+        bail!("the JPEG is truncated");
+    }
+    data.splice(position..position, segment);
+
+    Ok(())
+}
+
+/// Insert the EXIF data as eXIf chunk right after the IHDR chunk.
+fn insert_png_exif(data: &mut Vec<u8>, exif: &[u8]) -> anyhow::Result<()> {
+    // the 8 byte signature, followed by the IHDR chunk with 13 bytes of data
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+
+    let length = u32::try_from(exif.len())?;
+    let mut chunk = length.to_be_bytes().to_vec();
+    chunk.extend_from_slice(b"eXIf");
+    chunk.extend_from_slice(exif);
+    let crc = crc32(&chunk[4..]);
+    chunk.extend_from_slice(&crc.to_be_bytes());
+    data.splice(IHDR_END..IHDR_END, chunk);
+
+    Ok(())
+}
+
+/// The CRC-32 checksum of PNG chunks.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use image::Rgba;
+
+    use super::*;
+    use crate::image_entry::PastedImage;
+
+    /// An IFD entry with a single SHORT or LONG value in little endian.
+    fn entry(tag: u16, kind: u16, value: u32) -> Vec<u8> {
+        let mut entry = [tag.to_le_bytes(), kind.to_le_bytes()].concat();
+        entry.extend_from_slice(&1_u32.to_le_bytes());
+        match kind {
+            TYPE_SHORT => {
+                entry.extend_from_slice(&u16::try_from(value).unwrap().to_le_bytes());
+                entry.extend_from_slice(&[0, 0]);
+            }
+            _ => entry.extend_from_slice(&value.to_le_bytes()),
+        }
+
+        entry
+    }
+
+    /// EXIF data of a rotated 4000x3000 photo, with the dimensions in the EXIF IFD at 38.
+    fn photo_exif() -> Vec<u8> {
+        let mut exif = b"II".to_vec();
+        exif.extend_from_slice(&42_u16.to_le_bytes());
+        exif.extend_from_slice(&8_u32.to_le_bytes());
+
+        for ifd in [
+            [
+                entry(TAG_ORIENTATION, TYPE_SHORT, 6),
+                entry(TAG_EXIF_IFD, TYPE_LONG, 38),
+            ],
+            [
+                entry(TAG_PIXEL_X_DIMENSION, TYPE_SHORT, 4000),
+                entry(TAG_PIXEL_Y_DIMENSION, TYPE_LONG, 3000),
+            ],
+        ] {
+            exif.extend_from_slice(&2_u16.to_le_bytes());
+            exif.extend(ifd.concat());
+            exif.extend_from_slice(&0_u32.to_le_bytes());
+        }
+
+        exif
+    }
+
+    fn png_with_exif(exif: &[u8]) -> Vec<u8> {
+        let buffer = RgbaImage::from_pixel(20, 10, Rgba([200, 100, 50, 255]));
+        let mut data = Vec::new();
+        encode(PngEncoder::new(&mut data), &buffer, &Metadata::default()).unwrap();
+        insert_png_exif(&mut data, exif).unwrap();
+
+        data
+    }
+
+    #[test]
+    fn known_crcs() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn png_exif_chunk() {
+        let data = png_with_exif(b"abc");
+
+        // the eXIf chunk directly follows the 33 bytes of the signature and the IHDR chunk
+        assert_eq!(&data[33..41], b"\0\0\0\x03eXIf");
+        assert_eq!(&data[41..44], b"abc");
+        assert_eq!(data[44..48], 0x25F4_3C91_u32.to_be_bytes());
+        assert_eq!(
+            Metadata::decode(data).unwrap().exif.as_deref(),
+            Some(b"abc".as_slice())
+        );
+    }
+
+    #[test]
+    fn jpeg_exif_segment() {
+        let buffer = RgbaImage::from_pixel(16, 16, Rgba([200, 100, 50, 255]));
+        let rgb = DynamicImage::ImageRgba8(buffer).into_rgb8();
+        let mut data = Vec::new();
+        JpegEncoder::new(&mut data)
+            .write_image(rgb.as_raw(), 16, 16, ExtendedColorType::Rgb8)
+            .unwrap();
+        insert_jpeg_exif(&mut data, &photo_exif()).unwrap();
+
+        // right after the start of image marker and the JFIF segment
+        assert_eq!(&data[..4], &[0xFF, 0xD8, 0xFF, 0xE0]);
+        assert_eq!(&data[20..22], &[0xFF, 0xE1]);
+        assert_eq!(Metadata::decode(data).unwrap().exif, Some(photo_exif()));
+    }
+
+    #[test]
+    fn update_orientation_and_dimensions() {
+        let mut exif = photo_exif();
+        update_exif(&mut exif, (20, 10)).unwrap();
+
+        let mut expected = photo_exif();
+        expected[18..20].copy_from_slice(&1_u16.to_le_bytes());
+        expected[48..50].copy_from_slice(&20_u16.to_le_bytes());
+        expected[60..64].copy_from_slice(&10_u32.to_le_bytes());
+        assert_eq!(exif, expected);
+
+        // too large for the SHORT of the original
+        update_exif(&mut exif, (70_000, 10)).unwrap();
+        assert_eq!(exif[42..44], TYPE_LONG.to_le_bytes());
+        assert_eq!(exif[48..52], 70_000_u32.to_le_bytes());
+
+        assert!(update_exif(&mut b"not exif".to_vec(), (20, 10)).is_none());
+    }
+
+    #[test]
+    fn export_keeps_updated_exif() {
+        let image = ImageEntry::Pasted(PastedImage {
+            id: 1,
+            extension: "png",
+            data: Arc::from(png_with_exif(&photo_exif())),
+        });
+        let buffer = RgbaImage::from_pixel(20, 10, Rgba([200, 100, 50, 255]));
+        let mut expected = photo_exif();
+        update_exif(&mut expected, (10, 5)).unwrap();
+
+        for format in [ExportFormat::Png, ExportFormat::Jpeg] {
+            let options = ExportOptions {
+                format,
+                resize: Resize::Percentage(50),
+                ..ExportOptions::default()
+            };
+            let path = std::env::temp_dir().join(format!(
+                "{}-export-test-{}.{}",
+                std::process::id(),
+                format.name(),
+                format.extension()
+            ));
+            export(&buffer, Some(&image), false, &options, &path).unwrap();
+            let data = std::fs::read(&path).unwrap();
+            let _ = std::fs::remove_file(&path);
+
+            assert_eq!(image::load_from_memory(&data).unwrap().width(), 10);
+            assert_eq!(Metadata::decode(data).unwrap().exif, Some(expected.clone()));
+        }
+    }
+
+    #[test]
+    fn output_size() {
+        let size = |resize| {
+            ExportOptions {
+                resize,
+                ..ExportOptions::default()
+            }
+            .output_size((200, 100))
+        };
+
+        assert_eq!(size(Resize::Original), (200, 100));
+        assert_eq!(size(Resize::Percentage(50)), (100, 50));
+        assert_eq!(size(Resize::Percentage(150)), (300, 150));
+        assert_eq!(size(Resize::Percentage(0)), (1, 1));
+        assert_eq!(size(Resize::MaxDimension(50)), (50, 25));
+        assert_eq!(size(Resize::MaxDimension(1000)), (200, 100));
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, Read, Seek},
+    path::Path,
+};
+
+/// File extensions of the image formats that are always supported, see [OptionalFormat] for
+/// the others.
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+    "bmp", "dds", "exr", "gif", "hdr", "ico", "jpeg", "jpg", "pbm", "pgm", "png", "pnm", "ppm",
+    "svg", "svgz", "tga", "tif", "tiff", "webp",
+];
+
+/// Whether the path has the extension of a supported image format.
+///
+/// Used for finding images in directories and archives, where all other files are ignored.
+pub fn has_image_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        OptionalFormat::ALL
+            .into_iter()
+            .filter(|format| format.is_supported())
+            .flat_map(OptionalFormat::extensions)
+            .chain(IMAGE_EXTENSIONS)
+            .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+    })
+}
+
+/// Image formats whose support is optional and enabled via cargo features.
+///
+/// These are detected by their magic bytes, as not all of them are (correctly)
+/// detected by `imagesize` or the `image` crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptionalFormat {
+    Avif,
+    Heic,
+    Jxl,
+    Qoi,
+    Pdf,
+}
+
+impl Display for OptionalFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Avif => "AVIF",
+            Self::Heic => "HEIC",
+            Self::Jxl => "JXL",
+            Self::Qoi => "QOI",
+            Self::Pdf => "PDF",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl OptionalFormat {
+    const ALL: [Self; 5] = [Self::Avif, Self::Heic, Self::Jxl, Self::Qoi, Self::Pdf];
+
+    /// The file extensions of the format.
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Avif => &["avif"],
+            Self::Heic => &["heic", "heif"],
+            Self::Jxl => &["jxl"],
+            Self::Qoi => &["qoi"],
+            Self::Pdf => &["pdf"],
+        }
+    }
+
+    /// Detect the format of the file at the path by its magic bytes.
+    pub fn detect(path: &Path) -> Option<Self> {
+        Self::detect_reader(&mut File::open(path).ok()?).ok()?
+    }
+
+    /// Like [Self::detect], but for an image that's already opened, e.g. an archive entry.
+    /// The reader is rewound afterwards.
+    pub fn detect_reader(reader: &mut (impl Read + Seek)) -> io::Result<Option<Self>> {
+        // enough for the 'ftyp' box of HEIF files including a few compatible brands
+        let mut header = Vec::with_capacity(64);
+        reader.by_ref().take(64).read_to_end(&mut header)?;
+        reader.rewind()?;
+
+        Ok(Self::from_header(&header))
+    }
+
+    fn from_header(header: &[u8]) -> Option<Self> {
+        if header.starts_with(b"qoif") {
+            return Some(Self::Qoi);
+        }
+
+        if header.starts_with(b"%PDF-") {
+            return Some(Self::Pdf);
+        }
+
+        // JPEG XL codestream or container
+        if header.starts_with(&[0xFF, 0x0A])
+            || header.starts_with(&[0, 0, 0, 0x0C, b'J', b'X', b'L', b' '])
+        {
+            return Some(Self::Jxl);
+        }
+
+        // AVIF and HEIC are both based on the ISO base media file format (HEIF)
+        // and can only be told apart by the brands of their 'ftyp' box
+        if header.get(4..8) == Some(b"ftyp".as_slice()) {
+            return Self::from_ftyp_box(header);
+        }
+
+        None
+    }
+
+    fn from_ftyp_box(header: &[u8]) -> Option<Self> {
+        match header.get(8..12)? {
+            b"avif" | b"avis" => return Some(Self::Avif),
+            b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" => return Some(Self::Heic),
+            // generic HEIF brands, which AVIF files use as major brand as well
+            b"mif1" | b"msf1" => {}
+            _ => return None,
+        }
+
+        // the compatible brands follow the major brand and the minor version
+        let box_size = usize::try_from(u32::from_be_bytes(header[0..4].try_into().ok()?)).ok()?;
+        let compatible_brands = header
+            .get(16..box_size.min(header.len()))
+            .unwrap_or_default();
+        if compatible_brands
+            .chunks_exact(4)
+            .any(|brand| brand == b"avif" || brand == b"avis")
+        {
+            Some(Self::Avif)
+        } else {
+            Some(Self::Heic)
+        }
+    }
+
+    /// Whether support for the format has been enabled at compile time.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::Avif => cfg!(feature = "avif"),
+            Self::Heic => cfg!(feature = "heic"),
+            Self::Jxl => cfg!(feature = "jxl"),
+            Self::Qoi => cfg!(feature = "qoi"),
+            Self::Pdf => cfg!(feature = "pdf"),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Histograms of the color channels and the luminance of images.
+
+use image::RgbaImage;
+
+pub const BIN_COUNT: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Luminance,
+}
+
+impl Channel {
+    pub const ALL: [Self; 4] = [Self::Red, Self::Green, Self::Blue, Self::Luminance];
+}
+
+/// The number of pixels for each value (0-255) of each channel.
+pub struct Histogram {
+    red: [u32; BIN_COUNT],
+    green: [u32; BIN_COUNT],
+    blue: [u32; BIN_COUNT],
+    luminance: [u32; BIN_COUNT],
+}
+
+impl Histogram {
+    /// Count the values of all pixels of the images that aren't fully transparent, e.g. of
+    /// both pages of a spread.
+    ///
+    /// This iterates over all pixels, so it should be run in the background.
+    pub fn from_images(buffers: &[RgbaImage]) -> Self {
+        let mut histogram = Self {
+            red: [0; BIN_COUNT],
+            green: [0; BIN_COUNT],
+            blue: [0; BIN_COUNT],
+            luminance: [0; BIN_COUNT],
+        };
+
+        for pixel in buffers.iter().flat_map(RgbaImage::pixels) {
+            let [red, green, blue, alpha] = pixel.0;
+            if alpha == 0 {
+                continue;
+            }
+
+            histogram.red[usize::from(red)] += 1;
+            histogram.green[usize::from(green)] += 1;
+            histogram.blue[usize::from(blue)] += 1;
+            histogram.luminance[usize::from(luminance(red, green, blue))] += 1;
+        }
+
+        histogram
+    }
+
+    pub fn bins(&self, channel: Channel) -> &[u32; BIN_COUNT] {
+        match channel {
+            Channel::Red => &self.red,
+            Channel::Green => &self.green,
+            Channel::Blue => &self.blue,
+            Channel::Luminance => &self.luminance,
+        }
+    }
+}
+
+/// The relative luminance of the (gamma encoded) color, using the Rec. 709 coefficients.
+#[allow(clippy::cast_possible_truncation)]
+fn luminance(red: u8, green: u8, blue: u8) -> u8 {
+    ((2126 * u32::from(red) + 7152 * u32::from(green) + 722 * u32::from(blue)) / 10_000) as u8
+}
@@ -0,0 +1,127 @@
+use std::{
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use crate::archive;
+
+/// An image in the list of images, either a plain file, an entry of an archive,
+/// or an image that only exists in memory.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageEntry {
+    File(PathBuf),
+    ArchiveEntry { archive: PathBuf, name: String },
+    Pasted(PastedImage),
+}
+
+/// An image pasted from the clipboard, which isn't saved to disk (yet).
+#[derive(Clone)]
+pub struct PastedImage {
+    /// Sequential number to tell pasted images apart, starting at 1.
+    pub id: usize,
+    /// File extension matching the format of `data`, e.g. "png".
+    pub extension: &'static str,
+    /// The encoded image data.
+    pub data: Arc<[u8]>,
+}
+
+impl PastedImage {
+    pub fn new(extension: &'static str, data: Vec<u8>) -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            extension,
+            data: data.into(),
+        }
+    }
+
+    /// A file name for the image, e.g. "pasted-1.png".
+    pub fn file_name(&self) -> String {
+        format!("pasted-{}.{}", self.id, self.extension)
+    }
+}
+
+// compared by id only, as comparing (or hashing) the image data is expensive
+impl PartialEq for PastedImage {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for PastedImage {}
+
+impl Hash for PastedImage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl Debug for PastedImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PastedImage")
+            .field("id", &self.id)
+            .field("extension", &self.extension)
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+impl Display for ImageEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::ArchiveEntry { archive, name } => write!(f, "{}/{name}", archive.display()),
+            Self::Pasted(image) => write!(f, "{}", image.file_name()),
+        }
+    }
+}
+
+impl ImageEntry {
+    /// Create entries for the files, replacing archives with the images they contain.
+    pub fn from_paths(paths: Vec<PathBuf>) -> Vec<Self> {
+        paths
+            .into_iter()
+            .flat_map(|path| {
+                if !archive::is_archive(&path) {
+                    return vec![Self::File(path)];
+                }
+
+                match archive::list_images(&path) {
+                    Ok(names) => names
+                        .into_iter()
+                        .map(|name| Self::ArchiveEntry {
+                            archive: path.clone(),
+                            name,
+                        })
+                        .collect(),
+                    Err(err) => {
+                        eprintln!("Failed to read archive {}: {err}", path.display());
+                        Vec::new()
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// The file on disk, i.e. the archive for archive entries and nothing for pasted images.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::File(path) | Self::ArchiveEntry { archive: path, .. } => Some(path),
+            Self::Pasted(_) => None,
+        }
+    }
+
+    /// The name of the entry inside the archive.
+    pub fn archive_entry(&self) -> Option<&str> {
+        match self {
+            Self::File(_) | Self::Pasted(_) => None,
+            Self::ArchiveEntry { name, .. } => Some(name),
+        }
+    }
+}
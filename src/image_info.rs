@@ -1,6 +1,12 @@
-use std::{fmt::Display, fs::File, io::BufReader, os::unix::fs::MetadataExt, path::Path};
+use std::{
+    fmt::Display, fs::File, io::BufReader, os::unix::fs::MetadataExt, path::Path, time::Duration,
+};
 
-#[derive(Copy, Clone, Debug)]
+use serde::{Serialize, Serializer};
+
+use crate::{archive, format::OptionalFormat, image_entry::ImageEntry};
+
+#[derive(Copy, Clone, Debug, Serialize)]
 pub struct ImageResolution {
     pub width: usize,
     pub height: usize,
@@ -18,20 +24,157 @@ impl Display for ImageResolution {
 }
 
 impl ImageResolution {
+    /// Create a resolution from a (possibly fractional) size, e.g. the size of a vector image.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn from_size(width: f32, height: f32) -> Self {
+        Self {
+            width: width.round() as usize,
+            height: height.round() as usize,
+        }
+    }
+
     #[allow(clippy::cast_precision_loss)]
     pub fn aspect_ratio(&self) -> f32 {
         (self.width as f32) / (self.height as f32)
     }
 }
 
+/// Metadata of the camera that took the photo.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CameraInfo {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    /// Exposure time in seconds, e.g. "1/200".
+    pub exposure_time: Option<String>,
+    pub f_number: Option<f32>,
+    pub iso: Option<u32>,
+    /// Focal length in millimeters.
+    pub focal_length: Option<f32>,
+    pub date_time: Option<String>,
+}
+
+impl CameraInfo {
+    /// The camera name, e.g. "Canon EOS 5D".
+    pub fn name(&self) -> Option<String> {
+        match (&self.make, &self.model) {
+            // most vendors already include their name in the model
+            (Some(make), Some(model)) if model.starts_with(make.as_str()) => Some(model.clone()),
+            (Some(make), Some(model)) => Some(format!("{make} {model}")),
+            (make, model) => make.clone().or_else(|| model.clone()),
+        }
+    }
+
+    /// The exposure settings, e.g. "1/200s, f/2.8, ISO 100, 50mm".
+    pub fn exposure(&self) -> Option<String> {
+        let settings: Vec<_> = [
+            self.exposure_time.as_ref().map(|time| format!("{time}s")),
+            self.f_number.map(|f_number| format!("f/{f_number:.1}")),
+            self.iso.map(|iso| format!("ISO {iso}")),
+            self.focal_length
+                .map(|focal_length| format!("{focal_length:.0}mm")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        (!settings.is_empty()).then(|| settings.join(", "))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct ImageInfo {
     pub file_name: Option<String>,
     pub file_type: Option<String>,
     pub file_size: Option<u64>,
     pub resolution: Option<ImageResolution>,
+    /// Number of frames, only known after the image has been decoded.
+    pub frame_count: Option<usize>,
+    /// Duration of one loop of an animated image, only known after the image has been decoded.
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Option<Duration>,
+    /// Camera metadata, currently only available for RAW files.
+    pub camera: Option<CameraInfo>,
+    /// Number of pages of a document, only known after the image has been decoded.
+    pub page_count: Option<usize>,
+    /// File name of the archive containing the image, if any.
+    pub archive: Option<String>,
+    /// Name of the embedded color profile, only known after the image has been decoded.
+    pub color_profile: Option<String>,
+    /// Whether the image was converted from its color profile to the display profile.
+    pub color_managed: bool,
+    /// Why the color profile couldn't be applied.
+    pub color_error: Option<String>,
+}
+
+#[allow(clippy::ref_option)]
+fn serialize_millis<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    duration
+        .map(|duration| duration.as_millis())
+        .serialize(serializer)
 }
 
 impl ImageInfo {
+    pub fn from_entry(entry: &ImageEntry) -> Self {
+        match entry {
+            ImageEntry::File(path) => Self::from_file_path(path),
+            ImageEntry::ArchiveEntry { archive, name } => Self::from_archive_entry(archive, name),
+            ImageEntry::Pasted(image) => Self::from_data(image.file_name(), &image.data),
+        }
+    }
+
+    fn from_archive_entry(archive: &Path, name: &str) -> Self {
+        let data = archive::read_entry(archive, name).unwrap_or_default();
+
+        Self::from_archive_entry_data(archive, name, &data)
+    }
+
+    /// The info of an archive entry as far as it is known without reading (i.e. decompressing)
+    /// it, which [Self::from_entry] does.
+    pub fn from_archive_entry_name(archive: &Path, name: &str) -> Self {
+        Self::from_archive_entry_data(archive, name, &[])
+    }
+
+    fn from_archive_entry_data(archive: &Path, name: &str, data: &[u8]) -> Self {
+        let mut info = Self::from_data(name.to_string(), data);
+        info.archive = archive
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+
+        info
+    }
+
+    /// Create the info of an image that isn't a file on its own, from its encoded data.
+    fn from_data(file_name: String, data: &[u8]) -> Self {
+        let file_type = imagesize::image_type(data)
+            .ok()
+            .map(|file_type| format!("{file_type:?}").to_uppercase());
+
+        let resolution = imagesize::blob_size(data)
+            .ok()
+            .map(|resolution| ImageResolution {
+                width: resolution.width,
+                height: resolution.height,
+            });
+
+        Self {
+            file_name: Some(file_name),
+            file_type,
+            file_size: (!data.is_empty()).then_some(data.len() as u64),
+            resolution,
+            frame_count: None,
+            duration: None,
+            camera: None,
+            page_count: None,
+            archive: None,
+            color_profile: None,
+            color_managed: false,
+            color_error: None,
+        }
+    }
+
     pub fn from_file_path(path: &Path) -> Self {
         let file_name = path
             .file_name()
@@ -42,13 +185,17 @@ impl ImageInfo {
             .and_then(|file| file.metadata().ok())
             .map(|metadata| metadata.size());
 
-        let file_type = File::open(path)
-            .ok()
-            .and_then(|f| {
-                let reader = BufReader::new(f);
-                imagesize::reader_type(reader).ok()
-            })
-            .map(|file_type| format!("{file_type:?}").to_uppercase());
+        let file_type = OptionalFormat::detect(path)
+            .map(|format| format.to_string())
+            .or_else(|| {
+                File::open(path)
+                    .ok()
+                    .and_then(|f| {
+                        let reader = BufReader::new(f);
+                        imagesize::reader_type(reader).ok()
+                    })
+                    .map(|file_type| format!("{file_type:?}").to_uppercase())
+            });
 
         let resolution = imagesize::size(path)
             .ok()
@@ -57,11 +204,34 @@ impl ImageInfo {
                 height: resolution.height,
             });
 
-        Self {
+        #[cfg_attr(not(feature = "raw"), allow(unused_mut))]
+        let mut info = Self {
             file_name,
             file_type,
             file_size,
             resolution,
+            frame_count: None,
+            duration: None,
+            camera: None,
+            page_count: None,
+            archive: None,
+            color_profile: None,
+            color_managed: false,
+            color_error: None,
+        };
+
+        // imagesize only sees the TIFF structure of RAW files, i.e. the size of some thumbnail
+        #[cfg(feature = "raw")]
+        if crate::raw::is_raw(path)
+            && let Ok((sensor_resolution, camera)) = crate::raw::read_info(path)
+        {
+            info.file_type = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_uppercase());
+            info.resolution = sensor_resolution.or(info.resolution);
+            info.camera = Some(camera);
         }
+
+        info
     }
 }
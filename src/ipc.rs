@@ -0,0 +1,394 @@
+use std::{
+    cell::RefCell,
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{OnceLock, mpsc},
+    thread,
+};
+
+use anyhow::{anyhow, bail};
+use futures::{
+    StreamExt,
+    channel::mpsc::{UnboundedSender, unbounded},
+};
+use gpui::{AnyWindowHandle, App, AppContext, Entity, WeakEntity, Window};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::build_action_with_argument,
+    config::{Config, absolute_paths},
+    image_info::ImageInfo,
+    windows::main_window::{AppEvent, AppWindow},
+};
+
+/// A request sent by a client, encoded as a single line of JSON.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    /// Invoke an action from [crate::actions] by its name, e.g. `NextImage` or `RunCommand:0`,
+    /// see [crate::actions::build_action_with_argument].
+    Action { name: String },
+    /// Open the given paths, replacing the current images unless `append` is set.
+    Open {
+        paths: Vec<PathBuf>,
+        #[serde(default)]
+        append: bool,
+    },
+    /// Query the current image and its [ImageInfo].
+    GetCurrent,
+    /// Keep the connection open and receive an [Event] whenever something changes.
+    Subscribe,
+}
+
+/// A response to a [Request], or an [Event] for subscribed clients.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Response {
+    Ok,
+    Error {
+        message: String,
+    },
+    Current {
+        index: Option<usize>,
+        count: usize,
+        path: Option<PathBuf>,
+        /// The name of the image inside the archive at `path`, if it is an archive entry.
+        entry: Option<String>,
+        info: Option<ImageInfo>,
+    },
+    Event(Event),
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    ImageChanged {
+        index: Option<usize>,
+        count: usize,
+        path: Option<PathBuf>,
+        entry: Option<String>,
+    },
+}
+
+/// The socket this instance is listening on, which it has to remove when shutting down.
+static BOUND_SOCKET_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// The socket to listen on: either the configured one or, in single-instance mode, a default one.
+pub fn socket_path(config: &Config) -> Option<PathBuf> {
+    config.socket_path.clone().or_else(|| {
+        config.single_instance.then(|| {
+            let runtime_dir =
+                env::var_os("XDG_RUNTIME_DIR").map_or_else(env::temp_dir, PathBuf::from);
+            runtime_dir.join(concat!(env!("CARGO_PKG_NAME"), ".sock"))
+        })
+    })
+}
+
+/// A [Request] together with the channel for sending back the [Response](s).
+struct Message {
+    request: Request,
+    reply: mpsc::Sender<Response>,
+}
+
+/// Listen for clients on the socket and handle their requests on the main thread.
+pub fn start_server(
+    socket_path: &Path,
+    app_window: &Entity<AppWindow>,
+    window: AnyWindowHandle,
+    cx: &mut App,
+) -> anyhow::Result<()> {
+    let listener = bind(socket_path)?;
+    let _ = BOUND_SOCKET_PATH.set(socket_path.to_path_buf());
+    // the window might be closed without quitting the app, e.g. on macOS
+    cx.observe_release(app_window, |_, _| remove_socket())
+        .detach();
+    cx.on_app_quit(|_| {
+        remove_socket();
+        async {}
+    })
+    .detach();
+
+    let (sender, mut receiver) = unbounded();
+    thread::spawn(move || accept_connections(&listener, &sender));
+
+    let subscribers: Rc<RefCell<Vec<mpsc::Sender<Response>>>> = Rc::default();
+
+    // forward all events to the subscribed clients, dropping the disconnected ones
+    cx.subscribe(app_window, {
+        let subscribers = subscribers.clone();
+
+        move |app_window, event: &AppEvent, cx| {
+            let event = match event {
+                AppEvent::ImageChanged => {
+                    let app_window = app_window.read(cx);
+
+                    let image = app_window.selected_image();
+
+                    Event::ImageChanged {
+                        index: image.as_ref().map(|_| app_window.selected_index()),
+                        count: app_window.image_count(),
+                        path: image
+                            .as_ref()
+                            .and_then(|image| image.path().map(Path::to_path_buf)),
+                        entry: image
+                            .as_ref()
+                            .and_then(|image| image.archive_entry().map(str::to_string)),
+                    }
+                }
+            };
+
+            subscribers
+                .borrow_mut()
+                .retain(|subscriber| subscriber.send(Response::Event(event.clone())).is_ok());
+        }
+    })
+    .detach();
+
+    let app_window = app_window.downgrade();
+    cx.spawn(async move |cx| {
+        while let Some(Message { request, reply }) = receiver.next().await {
+            if matches!(request, Request::Subscribe) {
+                subscribers.borrow_mut().push(reply.clone());
+            }
+
+            let response = cx
+                .update_window(window, |_, window, cx| {
+                    handle_request(request, &app_window, window, cx)
+                })
+                .unwrap_or_else(|err| Response::Error {
+                    message: err.to_string(),
+                });
+
+            let _ = reply.send(response);
+        }
+    })
+    .detach();
+
+    Ok(())
+}
+
+/// Remove the socket this instance is listening on, if any, such that clients don't try
+/// to connect to it anymore.
+///
+/// Has to be called before exiting the process directly, as that skips the quit handlers.
+pub fn remove_socket() {
+    if let Some(socket_path) = BOUND_SOCKET_PATH.get() {
+        let _ = fs::remove_file(socket_path);
+    }
+}
+
+fn bind(socket_path: &Path) -> anyhow::Result<UnixListener> {
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).is_ok() {
+            bail!(
+                "another instance is already listening on {}",
+                socket_path.display()
+            );
+        }
+
+        // left over by an instance that didn't shut down properly
+        fs::remove_file(socket_path)?;
+    }
+
+    Ok(UnixListener::bind(socket_path)?)
+}
+
+fn handle_request(
+    request: Request,
+    app_window: &WeakEntity<AppWindow>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Response {
+    let result = match request {
+        Request::Action { name } => match build_action_with_argument(&name) {
+            Some(action) => {
+                window.dispatch_action(action, cx);
+                Ok(Response::Ok)
+            }
+            None => Err(anyhow!("unknown action \"{name}\"")),
+        },
+        Request::Open { paths, append } => app_window.update(cx, |app_window, cx| {
+            app_window.open_paths(paths, append, cx);
+            window.activate_window();
+
+            Response::Ok
+        }),
+        Request::GetCurrent => app_window.read_with(cx, |app_window, cx| {
+            let image = app_window.selected_image();
+
+            Response::Current {
+                index: image.as_ref().map(|_| app_window.selected_index()),
+                count: app_window.image_count(),
+                path: image
+                    .as_ref()
+                    .and_then(|image| image.path().map(Path::to_path_buf)),
+                entry: image
+                    .as_ref()
+                    .and_then(|image| image.archive_entry().map(str::to_string)),
+                info: app_window.image_info(cx),
+            }
+        }),
+        Request::Subscribe => Ok(Response::Ok),
+    };
+
+    result.unwrap_or_else(|err| Response::Error {
+        message: err.to_string(),
+    })
+}
+
+fn accept_connections(listener: &UnixListener, sender: &UnboundedSender<Message>) {
+    for stream in listener.incoming().flatten() {
+        let sender = sender.clone();
+
+        thread::spawn(move || {
+            if let Err(err) = handle_connection(stream, &sender) {
+                eprintln!("Control socket connection failed: {err}");
+            }
+        });
+    }
+}
+
+fn handle_connection(stream: UnixStream, sender: &UnboundedSender<Message>) -> anyhow::Result<()> {
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => request,
+            Err(err) => {
+                write_response(
+                    &mut writer,
+                    &Response::Error {
+                        message: err.to_string(),
+                    },
+                )?;
+                continue;
+            }
+        };
+        let is_subscription = matches!(request, Request::Subscribe);
+
+        let (reply, replies) = mpsc::channel();
+        sender
+            .unbounded_send(Message { request, reply })
+            .map_err(|_| anyhow!("the app has been closed"))?;
+
+        if is_subscription {
+            // forward all events until either the client or the app disconnects
+            for response in replies {
+                write_response(&mut writer, &response)?;
+            }
+            return Ok(());
+        }
+
+        if let Ok(response) = replies.recv() {
+            write_response(&mut writer, &response)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_response(writer: &mut impl Write, response: &Response) -> anyhow::Result<()> {
+    write_json_line(writer, response)
+}
+
+fn write_json_line(writer: &mut impl Write, value: &impl Serialize) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(value)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())?;
+
+    Ok(())
+}
+
+/// Whether the response line isn't an error.
+fn is_success(line: &str) -> anyhow::Result<bool> {
+    let response: serde_json::Value = serde_json::from_str(line)?;
+
+    Ok(response["type"] != "error")
+}
+
+/// Parse the message of the `msg` subcommand, see [crate::config::CliCommand::Msg].
+fn parse_message(message: &[String]) -> anyhow::Result<Request> {
+    // directories are expanded by the instance, such that reloading picks up new images
+    let paths = |paths: &[String]| {
+        let paths: Vec<_> = paths.iter().map(PathBuf::from).collect();
+        absolute_paths(&paths)
+    };
+
+    match message {
+        [json] if json.trim_start().starts_with('{') => Ok(serde_json::from_str(json)?),
+        [command, name] if command == "action" => Ok(Request::Action { name: name.clone() }),
+        [command, rest @ ..] if command == "open" => Ok(Request::Open {
+            paths: paths(rest),
+            append: false,
+        }),
+        [command, rest @ ..] if command == "append" => Ok(Request::Open {
+            paths: paths(rest),
+            append: true,
+        }),
+        [command] if command == "get-current" => Ok(Request::GetCurrent),
+        [command] if command == "subscribe" => Ok(Request::Subscribe),
+        _ => Err(anyhow!("invalid message \"{}\"", message.join(" "))),
+    }
+}
+
+/// Send the message to the instance listening on the socket and print the response(s) to stdout.
+///
+/// Returns `false` if the instance responded with an error.
+pub fn send_message(socket_path: &Path, message: &[String]) -> anyhow::Result<bool> {
+    let request = parse_message(message)?;
+    let is_subscription = matches!(request, Request::Subscribe);
+
+    let mut stream = UnixStream::connect(socket_path)?;
+    write_json_line(&mut stream, &request)?;
+
+    let mut success = true;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        println!("{line}");
+
+        success &= is_success(&line)?;
+
+        if !is_subscription {
+            break;
+        }
+    }
+
+    Ok(success)
+}
+
+/// Append the images to the instance listening on the socket, if there is one.
+///
+/// Returns `false` if no instance is running.
+pub fn forward_to_running_instance(
+    socket_path: &Path,
+    paths: Vec<PathBuf>,
+) -> anyhow::Result<bool> {
+    let Ok(mut stream) = UnixStream::connect(socket_path) else {
+        return Ok(false);
+    };
+
+    write_json_line(
+        &mut stream,
+        &Request::Open {
+            paths,
+            append: true,
+        },
+    )?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+
+    if !is_success(&response)? {
+        bail!("{}", response.trim());
+    }
+
+    Ok(true)
+}
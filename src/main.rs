@@ -3,7 +3,7 @@
 
 use crate::{
     actions::*,
-    config::{CONFIG, parse_cli_args_with_config},
+    config::{CONFIG, CliCommand, absolute_paths, parse_cli_args_with_config},
     windows::main_window::AppWindow,
 };
 use gpui::*;
@@ -16,16 +16,30 @@ extern crate rust_i18n;
 i18n!("locales", fallback = "en");
 
 mod actions;
+mod adjustments;
+mod archive;
+mod channels;
+mod clipboard;
+mod color;
+mod commands;
 mod config;
+mod crop;
+mod decoder;
+mod diff;
+mod export;
+mod format;
+mod histogram;
+mod image_entry;
 mod image_info;
+mod ipc;
+#[cfg(feature = "raw")]
+mod raw;
 mod widgets;
 mod windows;
 
 fn main() {
-    let app = Application::new();
-
-    let (paths, config) = match parse_cli_args_with_config() {
-        Ok((paths, config)) => (paths, config),
+    let (cli_options, config) = match parse_cli_args_with_config() {
+        Ok((cli_options, config)) => (cli_options, config),
         Err(err) => {
             eprintln!("Failed to parse config: {err}");
             exit(1);
@@ -33,19 +47,70 @@ fn main() {
     };
     CONFIG.set(config).unwrap();
 
-    let cwd = std::env::current_dir().unwrap_or_default();
-    let paths: Vec<_> = paths
-        .iter()
-        .map(|path| {
-            if path.is_relative() {
-                cwd.join(path)
-            } else {
-                path.clone()
+    match cli_options.command {
+        Some(CliCommand::Msg { socket, message }) => {
+            let Some(socket_path) = socket.or_else(|| ipc::socket_path(CONFIG.get().unwrap()))
+            else {
+                eprintln!("No socket path provided");
+                exit(1);
+            };
+
+            match ipc::send_message(&socket_path, &message) {
+                Ok(true) => exit(0),
+                Ok(false) => exit(1),
+                Err(err) => {
+                    eprintln!("Failed to send message: {err}");
+                    exit(1);
+                }
             }
-        })
-        .filter(|path| path.is_file())
-        .collect();
+        }
+        Some(CliCommand::Diff {
+            first,
+            second,
+            max_delta,
+            min_psnr,
+            min_ssim,
+        }) => {
+            let metrics = match diff::diff_files(&first, &second) {
+                Ok(metrics) => metrics,
+                Err(err) => {
+                    eprintln!("Failed to compare the images: {err}");
+                    exit(2);
+                }
+            };
+
+            println!("PSNR: {:.2} dB", metrics.psnr);
+            println!("SSIM: {:.5}", metrics.ssim);
+            println!("Max delta: {}", metrics.max_delta);
+
+            let exceeded = if max_delta.is_none() && min_psnr.is_none() && min_ssim.is_none() {
+                metrics.max_delta > 0
+            } else {
+                max_delta.is_some_and(|max_delta| metrics.max_delta > max_delta)
+                    || min_psnr.is_some_and(|min_psnr| metrics.psnr < min_psnr)
+                    || min_ssim.is_some_and(|min_ssim| metrics.ssim < min_ssim)
+            };
+            exit(i32::from(exceeded));
+        }
+        None => {}
+    }
+
+    let pick_mode = cli_options.pick;
+    let paths = absolute_paths(&cli_options.images);
+
+    let socket_path = ipc::socket_path(CONFIG.get().unwrap());
+    if CONFIG.get().unwrap().single_instance
+        && !pick_mode
+        && let Some(socket_path) = &socket_path
+    {
+        match ipc::forward_to_running_instance(socket_path, paths.clone()) {
+            Ok(true) => exit(0),
+            Ok(false) => {}
+            Err(err) => eprintln!("Failed to pass the images to the running instance: {err}"),
+        }
+    }
 
+    let app = Application::new();
     app.run(move |app| {
         let window_opts = WindowOptions {
             window_background: WindowBackgroundAppearance::Transparent,
@@ -69,7 +134,16 @@ fn main() {
             cx.open_window(window_opts, |window, cx| {
                 cx.bind_keys(build_key_bindings_from_config());
 
-                cx.new(|cx| AppWindow::new(window, cx, paths))
+                let app_window = cx.new(|cx| AppWindow::new(window, cx, paths, pick_mode));
+
+                if let Some(socket_path) = &socket_path
+                    && let Err(err) =
+                        ipc::start_server(socket_path, &app_window, window.window_handle(), cx)
+                {
+                    eprintln!("Failed to start the control socket: {err}");
+                }
+
+                app_window
             })?;
 
             Ok::<_, anyhow::Error>(())
@@ -0,0 +1,436 @@
+//! Camera RAW files, displayed using the full-size JPEG preview embedded by the camera.
+//!
+//! Most RAW formats are based on TIFF, hence only a minimal TIFF parser is needed
+//! to find the preview and the camera metadata.
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use image::{DynamicImage, ImageDecoder, ImageReader, metadata::Orientation};
+
+use crate::image_info::{CameraInfo, ImageResolution};
+
+const RAW_EXTENSIONS: &[&str] = &[
+    "arw", "cr2", "dng", "nef", "nrw", "orf", "pef", "raf", "rw2", "sr2", "srf",
+];
+const JPEG_EXTENSIONS: &[&str] = &["jpg", "jpeg"];
+
+/// Maximum number of IFDs to parse, protects against loops in broken files.
+const MAX_IFDS: usize = 64;
+
+// TIFF tags, see https://exiftool.org/TagNames/EXIF.html
+const TAG_NEW_SUBFILE_TYPE: u16 = 0x00FE;
+const TAG_IMAGE_WIDTH: u16 = 0x0100;
+const TAG_IMAGE_HEIGHT: u16 = 0x0101;
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_MAKE: u16 = 0x010F;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+const TAG_EXPOSURE_TIME: u16 = 0x829A;
+const TAG_F_NUMBER: u16 = 0x829D;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_ISO: u16 = 0x8827;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_FOCAL_LENGTH: u16 = 0x920A;
+
+const COMPRESSION_JPEG_OLD: u32 = 6;
+const COMPRESSION_JPEG: u32 = 7;
+
+pub fn is_raw(path: &Path) -> bool {
+    has_extension(path, RAW_EXTENSIONS)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension().is_some_and(|ext| {
+        extensions
+            .iter()
+            .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+    })
+}
+
+/// Remove RAW files that have a JPEG file with the same name next to them,
+/// such that RAW+JPEG pairs only appear once in the list of images.
+pub fn merge_raw_jpeg_pairs(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let jpegs: HashSet<PathBuf> = paths
+        .iter()
+        .filter(|path| has_extension(path, JPEG_EXTENSIONS))
+        .map(|path| path.with_extension(""))
+        .collect();
+
+    paths
+        .into_iter()
+        .filter(|path| !is_raw(path) || !jpegs.contains(&path.with_extension("")))
+        .collect()
+}
+
+/// A single entry of an IFD (image file directory).
+struct IfdEntry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    /// Offset of the value (or the offset to the value if it doesn't fit into 4 bytes).
+    value_field: u64,
+}
+
+struct TiffReader<'a> {
+    file: &'a File,
+    /// Offset of the TIFF header in the file, all offsets are relative to it.
+    base: u64,
+    little_endian: bool,
+}
+
+impl<'a> TiffReader<'a> {
+    fn new(file: &'a File, base: u64) -> Option<Self> {
+        let mut header = [0u8; 4];
+        file.read_exact_at(&mut header, base).ok()?;
+
+        let little_endian = match &header[..2] {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        let reader = Self {
+            file,
+            base,
+            little_endian,
+        };
+
+        // 42 for TIFF, but some vendors use their own magic (e.g. ORF and RW2)
+        match reader.u16(2)? {
+            42 | 0x55 | 0x4F52 | 0x5352 => Some(reader),
+            _ => None,
+        }
+    }
+
+    fn bytes(&self, offset: u64, len: usize) -> Option<Vec<u8>> {
+        let mut buffer = vec![0u8; len];
+        self.file
+            .read_exact_at(&mut buffer, self.base + offset)
+            .ok()?;
+
+        Some(buffer)
+    }
+
+    fn u16(&self, offset: u64) -> Option<u16> {
+        let bytes: [u8; 2] = self.bytes(offset, 2)?.try_into().ok()?;
+
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: u64) -> Option<u32> {
+        let bytes: [u8; 4] = self.bytes(offset, 4)?.try_into().ok()?;
+
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Parse the IFD at the offset, returning its entries and the offset of the next IFD.
+    fn ifd(&self, offset: u64) -> Option<(Vec<IfdEntry>, u64)> {
+        let entry_count = self.u16(offset)?;
+
+        let entries = (0..u64::from(entry_count))
+            .filter_map(|index| {
+                let entry_offset = offset + 2 + index * 12;
+
+                Some(IfdEntry {
+                    tag: self.u16(entry_offset)?,
+                    kind: self.u16(entry_offset + 2)?,
+                    count: self.u32(entry_offset + 4)?,
+                    value_field: entry_offset + 8,
+                })
+            })
+            .collect();
+        let next_offset = self.u32(offset + 2 + u64::from(entry_count) * 12)?;
+
+        Some((entries, u64::from(next_offset)))
+    }
+
+    /// Offset of the entry's value, which is stored inline if it fits into 4 bytes.
+    fn value_offset(&self, entry: &IfdEntry) -> Option<u64> {
+        let type_size = match entry.kind {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 | 13 => 4,
+            5 | 10 | 12 => 8,
+            _ => return None,
+        };
+
+        if type_size * u64::from(entry.count) <= 4 {
+            Some(entry.value_field)
+        } else {
+            self.u32(entry.value_field).map(u64::from)
+        }
+    }
+
+    /// All integer values of a SHORT, LONG or IFD entry.
+    fn integers(&self, entry: &IfdEntry) -> Vec<u32> {
+        let Some(offset) = self.value_offset(entry) else {
+            return Vec::new();
+        };
+
+        (0..u64::from(entry.count.min(256)))
+            .filter_map(|index| match entry.kind {
+                3 => self.u16(offset + index * 2).map(u32::from),
+                4 | 13 => self.u32(offset + index * 4),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn integer(&self, entry: &IfdEntry) -> Option<u32> {
+        self.integers(entry).first().copied()
+    }
+
+    fn rational(&self, entry: &IfdEntry) -> Option<(u32, u32)> {
+        let offset = self.value_offset(entry)?;
+        let denominator = self.u32(offset + 4)?;
+
+        (denominator != 0).then_some((self.u32(offset)?, denominator))
+    }
+
+    fn ascii(&self, entry: &IfdEntry) -> Option<String> {
+        let offset = self.value_offset(entry)?;
+        let bytes = self.bytes(offset, usize::try_from(entry.count.min(256)).ok()?)?;
+
+        let value = String::from_utf8_lossy(&bytes)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+/// A JPEG stream embedded in the RAW file.
+struct Preview {
+    offset: u64,
+    length: u64,
+}
+
+/// The metadata and previews found in a RAW file.
+#[derive(Default)]
+struct RawMetadata {
+    previews: Vec<Preview>,
+    sensor_resolution: Option<ImageResolution>,
+    orientation: Option<u8>,
+    camera: CameraInfo,
+}
+
+impl RawMetadata {
+    #[allow(clippy::cast_precision_loss)]
+    fn collect(&mut self, tiff: &TiffReader, first_ifd: u64) {
+        let mut pending = vec![first_ifd];
+        let mut visited = HashSet::new();
+
+        while let Some(offset) = pending.pop() {
+            if offset == 0 || visited.len() >= MAX_IFDS || !visited.insert(offset) {
+                continue;
+            }
+            let Some((entries, next_offset)) = tiff.ifd(offset) else {
+                continue;
+            };
+            pending.push(next_offset);
+
+            let find = |tag: u16| entries.iter().find(|entry| entry.tag == tag);
+            let integer = |tag: u16| find(tag).and_then(|entry| tiff.integer(entry));
+
+            // embedded JPEG, either via the JPEG tags or as a single JPEG compressed strip
+            if let (Some(offset), Some(length)) =
+                (integer(TAG_JPEG_OFFSET), integer(TAG_JPEG_LENGTH))
+            {
+                self.previews.push(Preview {
+                    offset: u64::from(offset),
+                    length: u64::from(length),
+                });
+            }
+            if matches!(
+                integer(TAG_COMPRESSION),
+                Some(COMPRESSION_JPEG_OLD | COMPRESSION_JPEG)
+            ) && let (Some(offset), Some(length)) =
+                (integer(TAG_STRIP_OFFSETS), integer(TAG_STRIP_BYTE_COUNTS))
+            {
+                self.previews.push(Preview {
+                    offset: u64::from(offset),
+                    length: u64::from(length),
+                });
+            }
+
+            // the raw sensor data is the largest image that isn't marked as reduced resolution preview
+            if integer(TAG_NEW_SUBFILE_TYPE).unwrap_or(0) & 1 == 0
+                && let (Some(width), Some(height)) =
+                    (integer(TAG_IMAGE_WIDTH), integer(TAG_IMAGE_HEIGHT))
+            {
+                let resolution = ImageResolution {
+                    width: width as usize,
+                    height: height as usize,
+                };
+                let pixels = |resolution: &ImageResolution| resolution.width * resolution.height;
+
+                if self
+                    .sensor_resolution
+                    .is_none_or(|current| pixels(&current) < pixels(&resolution))
+                {
+                    self.sensor_resolution = Some(resolution);
+                }
+            }
+
+            if self.orientation.is_none() {
+                self.orientation =
+                    integer(TAG_ORIENTATION).and_then(|value| u8::try_from(value).ok());
+            }
+
+            let camera = &mut self.camera;
+            let ascii = |tag: u16| find(tag).and_then(|entry| tiff.ascii(entry));
+            let rational = |tag: u16| find(tag).and_then(|entry| tiff.rational(entry));
+
+            camera.make = camera.make.take().or_else(|| ascii(TAG_MAKE));
+            camera.model = camera.model.take().or_else(|| ascii(TAG_MODEL));
+            camera.date_time = camera
+                .date_time
+                .take()
+                .or_else(|| ascii(TAG_DATE_TIME_ORIGINAL));
+            camera.iso = camera.iso.or_else(|| integer(TAG_ISO));
+            camera.exposure_time = camera.exposure_time.take().or_else(|| {
+                rational(TAG_EXPOSURE_TIME).map(|(numerator, denominator)| {
+                    if numerator < denominator {
+                        format!(
+                            "1/{}",
+                            (denominator as f32 / numerator.max(1) as f32).round()
+                        )
+                    } else {
+                        format!("{}", numerator as f32 / denominator as f32)
+                    }
+                })
+            });
+            camera.f_number = camera.f_number.or_else(|| {
+                rational(TAG_F_NUMBER)
+                    .map(|(numerator, denominator)| numerator as f32 / denominator as f32)
+            });
+            camera.focal_length = camera.focal_length.or_else(|| {
+                rational(TAG_FOCAL_LENGTH)
+                    .map(|(numerator, denominator)| numerator as f32 / denominator as f32)
+            });
+
+            // also parse the nested IFDs
+            for tag in [TAG_SUB_IFDS, TAG_EXIF_IFD] {
+                if let Some(entry) = find(tag) {
+                    pending.extend(tiff.integers(entry).into_iter().map(u64::from));
+                }
+            }
+        }
+    }
+}
+
+fn read_metadata(file: &File) -> anyhow::Result<RawMetadata> {
+    let mut metadata = RawMetadata::default();
+
+    if let Some(tiff) = TiffReader::new(file, 0) {
+        let first_ifd = tiff.u32(4).ok_or_else(|| anyhow!("invalid TIFF header"))?;
+        metadata.collect(&tiff, u64::from(first_ifd));
+
+        return Ok(metadata);
+    }
+
+    // Fujifilm RAF files aren't based on TIFF, but contain the offset of a JPEG preview in their header
+    let mut header = [0u8; 92];
+    file.read_exact_at(&mut header, 0)?;
+    if !header.starts_with(b"FUJIFILMCCD-RAW") {
+        return Err(anyhow!("unsupported RAW file"));
+    }
+
+    let offset = u64::from(u32::from_be_bytes(header[84..88].try_into()?));
+    let length = u64::from(u32::from_be_bytes(header[88..92].try_into()?));
+    metadata.previews.push(Preview { offset, length });
+
+    // the camera metadata is stored in the EXIF segment of the JPEG preview
+    let mut jpeg_start = vec![0u8; 64];
+    file.read_exact_at(&mut jpeg_start, offset)?;
+    if let Some(exif_position) = jpeg_start
+        .windows(6)
+        .position(|window| window == b"Exif\0\0")
+        && let Some(tiff) = TiffReader::new(file, offset + exif_position as u64 + 6)
+        && let Some(first_ifd) = tiff.u32(4)
+    {
+        metadata.collect(&tiff, u64::from(first_ifd));
+        // the sizes in the EXIF data are the ones of the preview, not of the sensor
+        metadata.sensor_resolution = None;
+    }
+
+    Ok(metadata)
+}
+
+/// The sensor resolution and the camera metadata of the RAW file.
+pub fn read_info(path: &Path) -> anyhow::Result<(Option<ImageResolution>, CameraInfo)> {
+    let metadata = read_metadata(&File::open(path)?)?;
+
+    Ok((metadata.sensor_resolution, metadata.camera))
+}
+
+/// Decode the largest JPEG preview embedded in the RAW file, also returning the ICC profile
+/// embedded in the preview.
+pub fn decode_preview(path: &Path) -> anyhow::Result<(DynamicImage, Option<Vec<u8>>)> {
+    let file = File::open(path)?;
+    let file_length = file.metadata()?.len();
+    let mut metadata = read_metadata(&file)?;
+
+    // try the largest preview first, some "previews" are in fact lossless JPEG sensor data
+    // that can't be decoded
+    metadata
+        .previews
+        .sort_by_key(|preview| std::cmp::Reverse(preview.length));
+
+    for preview in &metadata.previews {
+        // corrupt files could otherwise make us allocate up to 4 GiB
+        let end = preview.offset.checked_add(preview.length);
+        if end.is_none_or(|end| end > file_length) {
+            continue;
+        }
+        let Ok(length) = usize::try_from(preview.length) else {
+            continue;
+        };
+        let mut bytes = vec![0u8; length];
+        if file.read_exact_at(&mut bytes, preview.offset).is_err()
+            || !bytes.starts_with(&[0xFF, 0xD8])
+        {
+            continue;
+        }
+
+        let Ok(mut decoder) = ImageReader::new(std::io::Cursor::new(bytes))
+            .with_guessed_format()?
+            .into_decoder()
+        else {
+            continue;
+        };
+
+        // the orientation is usually only stored in the RAW file, not in the preview
+        let orientation = metadata
+            .orientation
+            .and_then(Orientation::from_exif)
+            .or_else(|| decoder.orientation().ok())
+            .unwrap_or(Orientation::NoTransforms);
+        let icc_profile = decoder.icc_profile().ok().flatten();
+
+        if let Ok(mut image) = DynamicImage::from_decoder(decoder) {
+            image.apply_orientation(orientation);
+            return Ok((image, icc_profile));
+        }
+    }
+
+    Err(anyhow!("no embedded preview found"))
+}
@@ -0,0 +1,44 @@
+use gpui::*;
+
+use crate::{adjustments::Adjustments, config::CONFIG};
+
+/// Shows the adjustments of the shown image, as long as they change anything.
+pub struct AdjustmentsWidget {
+    adjustments: Adjustments,
+}
+
+impl AdjustmentsWidget {
+    pub fn new(adjustments: Adjustments) -> Self {
+        Self { adjustments }
+    }
+}
+
+impl Render for AdjustmentsWidget {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        let adjustments = self.adjustments;
+
+        div()
+            .border(px(2.0))
+            .border_color(CONFIG.get().unwrap().theme.primary.into_rgba())
+            .rounded_md()
+            .px_2()
+            .bg(CONFIG.get().unwrap().theme.surface.into_rgba())
+            .text_color(CONFIG.get().unwrap().theme.foreground.into_rgba())
+            .flex_col()
+            .child(
+                t!("adjustments.exposure", "value" => format!("{:+.2}", adjustments.exposure))
+                    .to_string(),
+            )
+            .child(
+                t!("adjustments.contrast", "value" => format!("{:.2}", adjustments.contrast))
+                    .to_string(),
+            )
+            .child(
+                t!("adjustments.gamma", "value" => format!("{:.2}", adjustments.gamma)).to_string(),
+            )
+            .child(
+                t!("adjustments.saturation", "value" => format!("{:.2}", adjustments.saturation))
+                    .to_string(),
+            )
+    }
+}
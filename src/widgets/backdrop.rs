@@ -0,0 +1,58 @@
+use gpui::{prelude::FluentBuilder, *};
+
+use crate::config::{Backdrop, CONFIG};
+
+/// Show the backdrop behind the image element, which determines the size of the backdrop.
+pub fn with_backdrop(image: impl IntoElement, backdrop: Backdrop) -> Div {
+    div()
+        .relative()
+        .map(|container| match backdrop {
+            Backdrop::Checkerboard => container.child(
+                canvas(
+                    |_bounds, _window, _cx| {},
+                    |bounds, (), window, _cx| paint_checkerboard(bounds, window),
+                )
+                .absolute()
+                .size_full(),
+            ),
+            Backdrop::Solid => container.bg(CONFIG.get().unwrap().theme.backdrop_color.into_rgba()),
+            Backdrop::Transparent => container,
+        })
+        .child(image)
+}
+
+/// Paint the checkerboard cells, skipping those outside of the visible area.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn paint_checkerboard(bounds: Bounds<Pixels>, window: &mut Window) {
+    let theme = &CONFIG.get().unwrap().theme;
+    let cell_size = px(theme.checkerboard_size.max(1) as f32);
+    let dark = Hsla::from(theme.checkerboard_dark.into_rgba());
+
+    window.paint_quad(fill(
+        bounds,
+        Hsla::from(theme.checkerboard_light.into_rgba()),
+    ));
+
+    let visible = bounds.intersect(&window.content_mask().bounds);
+    let first_column = ((visible.left() - bounds.left()) / cell_size).floor() as i64;
+    let last_column = ((visible.right() - bounds.left()) / cell_size).ceil() as i64;
+    let first_row = ((visible.top() - bounds.top()) / cell_size).floor() as i64;
+    let last_row = ((visible.bottom() - bounds.top()) / cell_size).ceil() as i64;
+
+    for row in first_row..last_row {
+        for column in first_column..last_column {
+            if (row + column) % 2 == 0 {
+                continue;
+            }
+
+            let cell = Bounds::new(
+                point(
+                    bounds.left() + cell_size * column as f32,
+                    bounds.top() + cell_size * row as f32,
+                ),
+                size(cell_size, cell_size),
+            );
+            window.paint_quad(fill(cell.intersect(&bounds), dark));
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use gpui::{prelude::FluentBuilder, *};
+
+use crate::{
+    config::CONFIG,
+    crop::{Crop, CropField},
+};
+
+/// Shows the crop rectangle, whose values can be typed in after pressing Tab.
+pub struct CropWidget {
+    crop: Crop,
+}
+
+impl CropWidget {
+    pub fn new(crop: Crop) -> Self {
+        Self { crop }
+    }
+}
+
+impl Render for CropWidget {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        let fields = CropField::ALL.map(|field| {
+            let editing = self
+                .crop
+                .editing
+                .as_ref()
+                .filter(|(edited_field, _)| *edited_field == field);
+            let value = match editing {
+                Some((_, digits)) => digits.clone(),
+                None => self
+                    .crop
+                    .rect
+                    .map_or_else(|| "-".to_string(), |rect| field.value(rect).to_string()),
+            };
+
+            div().flex().gap_1().child(field.name()).child(
+                div()
+                    .min_w_10()
+                    .px_1()
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(if editing.is_some() {
+                        CONFIG.get().unwrap().theme.primary.into_rgba()
+                    } else {
+                        CONFIG.get().unwrap().theme.surface.into_rgba()
+                    })
+                    .child(value),
+            )
+        });
+
+        div()
+            .border(px(2.0))
+            .border_color(CONFIG.get().unwrap().theme.primary.into_rgba())
+            .rounded_md()
+            .px_2()
+            .bg(CONFIG.get().unwrap().theme.surface.into_rgba())
+            .text_color(CONFIG.get().unwrap().theme.foreground.into_rgba())
+            .flex_col()
+            .child(t!("crop.aspect_ratio", "ratio" => self.crop.aspect_ratio.name()).to_string())
+            .child(div().flex().gap_2().children(fields))
+            .when(self.crop.rect.is_none(), |container| {
+                container.child(t!("crop.hint").to_string())
+            })
+    }
+}
@@ -0,0 +1,37 @@
+use gpui::*;
+
+use crate::{config::CONFIG, diff::DiffMetrics};
+
+/// Shows how much the compared images differ, or why that couldn't be determined.
+pub struct DiffMetricsWidget {
+    metrics: Result<DiffMetrics, String>,
+}
+
+impl DiffMetricsWidget {
+    pub fn new(metrics: Result<DiffMetrics, String>) -> Self {
+        Self { metrics }
+    }
+}
+
+impl Render for DiffMetricsWidget {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        let container = div()
+            .border(px(2.0))
+            .border_color(CONFIG.get().unwrap().theme.primary.into_rgba())
+            .rounded_md()
+            .px_2()
+            .bg(CONFIG.get().unwrap().theme.surface.into_rgba())
+            .text_color(CONFIG.get().unwrap().theme.foreground.into_rgba())
+            .flex_col();
+
+        match &self.metrics {
+            Ok(metrics) => container
+                .child(t!("compare.psnr", "value" => format!("{:.2}", metrics.psnr)).to_string())
+                .child(t!("compare.ssim", "value" => format!("{:.5}", metrics.ssim)).to_string())
+                .child(t!("compare.max_delta", "value" => metrics.max_delta).to_string()),
+            Err(err) => container
+                .text_color(CONFIG.get().unwrap().theme.error.into_rgba())
+                .child(err.clone()),
+        }
+    }
+}
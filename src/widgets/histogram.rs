@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use gpui::{prelude::FluentBuilder, *};
+
+use crate::{
+    actions::{ToggleHistogramChannel, ToggleHistogramScale},
+    config::CONFIG,
+    histogram::{BIN_COUNT, Channel, Histogram},
+};
+
+const WIDTH: f32 = 256.0;
+const HEIGHT: f32 = 128.0;
+
+/// Which channels of the histogram are shown and how.
+#[derive(Clone, Copy, Debug)]
+pub struct HistogramSettings {
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+    pub luminance: bool,
+    /// Whether the counts are scaled logarithmically, making small counts visible.
+    pub logarithmic: bool,
+}
+
+impl Default for HistogramSettings {
+    fn default() -> Self {
+        Self {
+            red: true,
+            green: true,
+            blue: true,
+            luminance: true,
+            logarithmic: false,
+        }
+    }
+}
+
+impl HistogramSettings {
+    pub fn is_shown(&self, channel: Channel) -> bool {
+        match channel {
+            Channel::Red => self.red,
+            Channel::Green => self.green,
+            Channel::Blue => self.blue,
+            Channel::Luminance => self.luminance,
+        }
+    }
+
+    pub fn toggle(&mut self, channel: Channel) {
+        let shown = match channel {
+            Channel::Red => &mut self.red,
+            Channel::Green => &mut self.green,
+            Channel::Blue => &mut self.blue,
+            Channel::Luminance => &mut self.luminance,
+        };
+        *shown = !*shown;
+    }
+}
+
+fn channel_color(channel: Channel) -> Rgba {
+    let theme = &CONFIG.get().unwrap().theme;
+
+    match channel {
+        Channel::Red => theme.red,
+        Channel::Green => theme.green,
+        Channel::Blue => theme.blue,
+        Channel::Luminance => theme.foreground,
+    }
+    .into_rgba()
+}
+
+fn channel_label(channel: Channel) -> String {
+    match channel {
+        Channel::Red => t!("histogram.red"),
+        Channel::Green => t!("histogram.green"),
+        Channel::Blue => t!("histogram.blue"),
+        Channel::Luminance => t!("histogram.luminance"),
+    }
+    .to_string()
+}
+
+pub struct HistogramWidget {
+    histogram: Arc<Histogram>,
+    settings: HistogramSettings,
+}
+
+impl HistogramWidget {
+    pub fn new(histogram: Arc<Histogram>, settings: HistogramSettings) -> Self {
+        Self {
+            histogram,
+            settings,
+        }
+    }
+}
+
+impl Render for HistogramWidget {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        let histogram = self.histogram.clone();
+        let settings = self.settings;
+
+        div()
+            .border(px(2.0))
+            .border_color(CONFIG.get().unwrap().theme.primary.into_rgba())
+            .rounded_md()
+            .p_2()
+            .bg(CONFIG.get().unwrap().theme.surface.into_rgba())
+            .text_color(CONFIG.get().unwrap().theme.foreground.into_rgba())
+            .flex_col()
+            .gap_1()
+            .child(
+                canvas(
+                    |_bounds, _window, _cx| {},
+                    move |bounds, (), window, _cx| {
+                        paint_histogram(&histogram, settings, bounds, window);
+                    },
+                )
+                .w(px(WIDTH))
+                .h(px(HEIGHT)),
+            )
+            .child(
+                div()
+                    .flex()
+                    .gap_2()
+                    .children(
+                        Channel::ALL
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, channel)| {
+                                div()
+                                    .id(("channel", index))
+                                    .cursor_pointer()
+                                    .text_color(channel_color(channel))
+                                    .when(!settings.is_shown(channel), |div| div.opacity(0.4))
+                                    .child(channel_label(channel))
+                                    .on_click(move |_, window, cx| {
+                                        window.dispatch_action(
+                                            Box::new(ToggleHistogramChannel { channel }),
+                                            cx,
+                                        );
+                                    })
+                            }),
+                    )
+                    .child(
+                        div()
+                            .id("scale")
+                            .ml_auto()
+                            .cursor_pointer()
+                            .child(if settings.logarithmic {
+                                t!("histogram.logarithmic").to_string()
+                            } else {
+                                t!("histogram.linear").to_string()
+                            })
+                            .on_click(|_, window, cx| {
+                                window.dispatch_action(Box::new(ToggleHistogramScale), cx);
+                            }),
+                    ),
+            )
+    }
+}
+
+/// Paint the shown channels as translucent areas on top of each other.
+#[allow(clippy::cast_precision_loss)]
+fn paint_histogram(
+    histogram: &Histogram,
+    settings: HistogramSettings,
+    bounds: Bounds<Pixels>,
+    window: &mut Window,
+) {
+    let scale = |count: u32| {
+        if settings.logarithmic {
+            f64::from(count).ln_1p()
+        } else {
+            f64::from(count)
+        }
+    };
+
+    let channels: Vec<_> = Channel::ALL
+        .into_iter()
+        .filter(|channel| settings.is_shown(*channel))
+        .collect();
+    let max = channels
+        .iter()
+        .flat_map(|channel| histogram.bins(*channel).iter().copied())
+        .map(scale)
+        .fold(0.0, f64::max);
+    if max <= 0.0 {
+        return;
+    }
+
+    let bin_width = bounds.size.width / BIN_COUNT as f32;
+    for channel in channels {
+        let mut builder = PathBuilder::fill();
+        builder.move_to(bounds.bottom_left());
+        for (index, count) in histogram.bins(channel).iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let height = bounds.size.height * (scale(*count) / max) as f32;
+            let x = bounds.left() + bin_width * index as f32;
+            let y = bounds.bottom() - height;
+
+            builder.line_to(point(x, y));
+            builder.line_to(point(x + bin_width, y));
+        }
+        builder.line_to(bounds.bottom_right());
+        builder.close();
+
+        if let Ok(path) = builder.build() {
+            let color = Rgba {
+                a: 0.5,
+                ..channel_color(channel)
+            };
+            window.paint_path(path, Hsla::from(color));
+        }
+    }
+}
@@ -1,17 +1,26 @@
-use std::path::PathBuf;
-
 use bytesize::ByteSize;
 use gpui::{prelude::FluentBuilder, *};
 
-use crate::{config::CONFIG, image_info::ImageInfo};
+use crate::{
+    channels::ChannelView,
+    config::CONFIG,
+    image_info::{CameraInfo, ImageInfo},
+    widgets::zoomable_image::Playback,
+};
 
 pub struct ImageInfoWidget {
-    path: PathBuf,
+    info: ImageInfo,
+    playback: Option<Playback>,
+    channel: ChannelView,
 }
 
 impl ImageInfoWidget {
-    pub fn new(path: PathBuf) -> Self {
-        Self { path }
+    pub fn new(info: ImageInfo, playback: Option<Playback>, channel: ChannelView) -> Self {
+        Self {
+            info,
+            playback,
+            channel,
+        }
     }
 }
 
@@ -21,7 +30,7 @@ impl Render for ImageInfoWidget {
         _window: &mut gpui::Window,
         _cx: &mut gpui::Context<Self>,
     ) -> impl gpui::IntoElement {
-        let img_info = ImageInfo::from_file_path(&self.path);
+        let img_info = self.info.clone();
 
         div()
             .border(px(2.0))
@@ -34,6 +43,9 @@ impl Render for ImageInfoWidget {
             .when_some(img_info.file_name, |div, file_name| {
                 div.child(t!("image.filename", "filename" => file_name).to_string())
             })
+            .when_some(img_info.archive, |div, archive| {
+                div.child(t!("image.archive", "archive" => archive).to_string())
+            })
             .when_some(img_info.file_type, |div, file_type| {
                 div.child(t!("image.type", "type" => file_type).to_string())
             })
@@ -43,5 +55,76 @@ impl Render for ImageInfoWidget {
             .when_some(img_info.file_size, |div, file_size| {
                 div.child(t!("image.size", "size" => ByteSize::b(file_size)).to_string())
             })
+            .when_some(img_info.color_profile, |div, color_profile| {
+                div.child(
+                    if img_info.color_managed {
+                        t!("image.color_profile", "profile" => color_profile)
+                    } else {
+                        t!("image.color_profile_unmanaged", "profile" => color_profile)
+                    }
+                    .to_string(),
+                )
+            })
+            .when_some(img_info.color_error, |container, error| {
+                container.child(
+                    div()
+                        .text_color(CONFIG.get().unwrap().theme.error.into_rgba())
+                        .child(t!("image.color_profile_error", "error" => error).to_string()),
+                )
+            })
+            .when_some(
+                img_info.camera.as_ref().and_then(CameraInfo::name),
+                |div, camera| div.child(t!("image.camera", "camera" => camera).to_string()),
+            )
+            .when_some(
+                img_info.camera.as_ref().and_then(CameraInfo::exposure),
+                |div, exposure| div.child(t!("image.exposure", "exposure" => exposure).to_string()),
+            )
+            .when_some(
+                img_info
+                    .camera
+                    .as_ref()
+                    .and_then(|camera| camera.date_time.clone()),
+                |div, date_time| div.child(t!("image.date", "date" => date_time).to_string()),
+            )
+            .when_some(img_info.duration, |div, duration| {
+                div.child(
+                    t!("image.duration", "duration" => format!("{:.2}s", duration.as_secs_f32()))
+                        .to_string(),
+                )
+            })
+            .when_some(
+                self.playback.zip(img_info.frame_count),
+                |div, (playback, frame_count)| {
+                    div.child(
+                        t!(
+                            "image.frame",
+                            "frame" => playback.frame_index + 1,
+                            "count" => frame_count
+                        )
+                        .to_string(),
+                    )
+                    .child(
+                        t!(
+                            "image.playback",
+                            "state" => if playback.playing {
+                                t!("playback.playing")
+                            } else {
+                                t!("playback.paused")
+                            },
+                            "speed" => playback.speed,
+                            "repeat" => if playback.looping {
+                                t!("playback.loop")
+                            } else {
+                                t!("playback.once")
+                            }
+                        )
+                        .to_string(),
+                    )
+                },
+            )
+            .when(self.channel != ChannelView::All, |div| {
+                div.child(t!("image.channel", "channel" => self.channel.name()).to_string())
+            })
     }
 }
@@ -1,2 +1,10 @@
+pub mod adjustments;
+pub mod backdrop;
+pub mod crop;
+pub mod diff_metrics;
+pub mod histogram;
 pub mod image_info;
+pub mod pixel_grid;
+pub mod pixel_inspector;
+pub mod save_as;
 pub mod zoomable_image;
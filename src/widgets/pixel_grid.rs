@@ -0,0 +1,47 @@
+use gpui::*;
+
+use crate::config::CONFIG;
+
+/// Lines between the pixels of an image of `image_size`, to be laid over the image element.
+pub fn pixel_grid(image_size: (u32, u32)) -> impl IntoElement {
+    canvas(
+        |_bounds, _window, _cx| {},
+        move |bounds, (), window, _cx| paint_pixel_grid(bounds, image_size, window),
+    )
+    .absolute()
+    .size_full()
+}
+
+/// Paint the lines, skipping those outside of the visible area.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn paint_pixel_grid(bounds: Bounds<Pixels>, (width, height): (u32, u32), window: &mut Window) {
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let color = Hsla::from(CONFIG.get().unwrap().theme.surface.into_rgba());
+    let cell_width = bounds.size.width / width as f32;
+    let cell_height = bounds.size.height / height as f32;
+    let line_width = px(1.0);
+
+    let visible = bounds.intersect(&window.content_mask().bounds);
+    let first_column = (((visible.left() - bounds.left()) / cell_width).floor() as i64).max(1);
+    let last_column = ((visible.right() - bounds.left()) / cell_width).ceil() as i64;
+    let first_row = (((visible.top() - bounds.top()) / cell_height).floor() as i64).max(1);
+    let last_row = ((visible.bottom() - bounds.top()) / cell_height).ceil() as i64;
+
+    for column in first_column..last_column.min(i64::from(width)) {
+        let line = Bounds::new(
+            point(bounds.left() + cell_width * column as f32, visible.top()),
+            size(line_width, visible.size.height),
+        );
+        window.paint_quad(fill(line, color));
+    }
+    for row in first_row..last_row.min(i64::from(height)) {
+        let line = Bounds::new(
+            point(visible.left(), bounds.top() + cell_height * row as f32),
+            size(visible.size.width, line_width),
+        );
+        window.paint_quad(fill(line, color));
+    }
+}
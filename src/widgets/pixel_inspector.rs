@@ -0,0 +1,53 @@
+use gpui::*;
+
+use crate::{config::CONFIG, widgets::zoomable_image::InspectedPixel};
+
+pub struct PixelInspectorWidget {
+    pixel: InspectedPixel,
+}
+
+impl PixelInspectorWidget {
+    pub fn new(pixel: InspectedPixel) -> Self {
+        Self { pixel }
+    }
+}
+
+impl Render for PixelInspectorWidget {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        let color = self.pixel.color;
+        let hsla = Hsla::from(color.into_rgba());
+
+        div()
+            .border(px(2.0))
+            .border_color(CONFIG.get().unwrap().theme.primary.into_rgba())
+            .rounded_md()
+            .px_2()
+            .bg(CONFIG.get().unwrap().theme.surface.into_rgba())
+            .text_color(CONFIG.get().unwrap().theme.foreground.into_rgba())
+            .flex_col()
+            .child(t!("inspector.position", "x" => self.pixel.x, "y" => self.pixel.y).to_string())
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .size_4()
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(CONFIG.get().unwrap().theme.foreground.into_rgba())
+                            .bg(color.into_rgba()),
+                    )
+                    .child(color.to_string()),
+            )
+            .child(format!("rgb({}, {}, {})", color.r, color.g, color.b))
+            .child(format!(
+                "hsl({}, {}%, {}%)",
+                (hsla.h * 360.0).round() as u32,
+                (hsla.s * 100.0).round() as u32,
+                (hsla.l * 100.0).round() as u32
+            ))
+    }
+}
@@ -0,0 +1,245 @@
+use gpui::{prelude::FluentBuilder, *};
+
+use crate::{
+    config::CONFIG,
+    export::{ExportFormat, ExportOptions, Resize, ResizeFilter},
+};
+
+const QUALITY_STEP: u8 = 5;
+const PERCENTAGE_STEP: u32 = 10;
+const MAX_PERCENTAGE: u32 = 400;
+const MAX_DIMENSION_STEP: u32 = 256;
+
+/// Emitted by the [SaveAsDialog] once the user made a choice.
+pub enum SaveAsEvent {
+    Save(ExportOptions),
+    Cancel,
+}
+
+/// Lets the user choose the format and the size the shown image is saved as.
+pub struct SaveAsDialog {
+    options: ExportOptions,
+    /// The size of the image before resizing, for showing the size of the saved image.
+    image_size: (u32, u32),
+}
+
+impl EventEmitter<SaveAsEvent> for SaveAsDialog {}
+
+impl SaveAsDialog {
+    pub fn new(options: ExportOptions, image_size: (u32, u32)) -> Self {
+        Self {
+            options,
+            image_size,
+        }
+    }
+
+    fn update(&mut self, cx: &mut Context<Self>, update: impl FnOnce(&mut ExportOptions)) {
+        update(&mut self.options);
+
+        cx.notify();
+    }
+
+    /// A button that changes the options when clicked.
+    fn button(
+        &self,
+        cx: &mut Context<Self>,
+        id: impl Into<ElementId>,
+        label: impl Into<SharedString>,
+        selected: bool,
+        update: impl Fn(&mut ExportOptions) + 'static,
+    ) -> Stateful<Div> {
+        option_button(id, label, selected).on_click(cx.listener(move |this, _, _window, cx| {
+            this.update(cx, &update);
+        }))
+    }
+}
+
+impl Render for SaveAsDialog {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let options = self.options;
+        let (width, height) = options.output_size(self.image_size);
+
+        div()
+            .border(px(2.0))
+            .border_color(CONFIG.get().unwrap().theme.primary.into_rgba())
+            .rounded_md()
+            .p_2()
+            .bg(CONFIG.get().unwrap().theme.surface.into_rgba())
+            .text_color(CONFIG.get().unwrap().theme.foreground.into_rgba())
+            .flex()
+            .flex_col()
+            .gap_2()
+            .child(t!("save_as.title").to_string())
+            .child(
+                row(t!("save_as.format").to_string()).children(
+                    ExportFormat::ALL
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, format)| {
+                            self.button(
+                                cx,
+                                ("format", index),
+                                format.name(),
+                                options.format == format,
+                                move |options| options.format = format,
+                            )
+                        }),
+                ),
+            )
+            .when(options.format == ExportFormat::Jpeg, |dialog| {
+                dialog.child(
+                    row(t!("save_as.quality").to_string())
+                        .child(self.button(cx, "quality_down", "-", false, |options| {
+                            options.jpeg_quality =
+                                options.jpeg_quality.saturating_sub(QUALITY_STEP).max(1);
+                        }))
+                        .child(options.jpeg_quality.to_string())
+                        .child(self.button(cx, "quality_up", "+", false, |options| {
+                            options.jpeg_quality = (options.jpeg_quality + QUALITY_STEP).min(100);
+                        })),
+                )
+            })
+            .child(
+                row(t!("save_as.resize").to_string())
+                    .child(self.button(
+                        cx,
+                        "resize_original",
+                        t!("save_as.resize_original").to_string(),
+                        options.resize == Resize::Original,
+                        |options| options.resize = Resize::Original,
+                    ))
+                    .child(self.button(
+                        cx,
+                        "resize_percentage",
+                        t!("save_as.resize_percentage").to_string(),
+                        matches!(options.resize, Resize::Percentage(_)),
+                        |options| options.resize = Resize::Percentage(50),
+                    ))
+                    .child(self.button(
+                        cx,
+                        "resize_max_dimension",
+                        t!("save_as.resize_max_dimension").to_string(),
+                        matches!(options.resize, Resize::MaxDimension(_)),
+                        |options| options.resize = Resize::MaxDimension(2048),
+                    )),
+            )
+            .when(options.resize != Resize::Original, |dialog| {
+                let value = match options.resize {
+                    Resize::Percentage(percentage) => format!("{percentage}%"),
+                    Resize::MaxDimension(max) => format!("{max} px"),
+                    Resize::Original => String::new(),
+                };
+
+                dialog
+                    .child(
+                        row(String::new())
+                            .child(self.button(cx, "resize_down", "-", false, |options| {
+                                options.resize = step_resize(options.resize, false);
+                            }))
+                            .child(value)
+                            .child(self.button(cx, "resize_up", "+", false, |options| {
+                                options.resize = step_resize(options.resize, true);
+                            })),
+                    )
+                    .child(
+                        row(t!("save_as.filter").to_string()).children(
+                            ResizeFilter::ALL
+                                .into_iter()
+                                .enumerate()
+                                .map(|(index, filter)| {
+                                    self.button(
+                                        cx,
+                                        ("filter", index),
+                                        filter.name(),
+                                        options.filter == filter,
+                                        move |options| options.filter = filter,
+                                    )
+                                }),
+                        ),
+                    )
+            })
+            .child(
+                row(t!("save_as.metadata").to_string())
+                    .child(self.button(
+                        cx,
+                        "keep_metadata",
+                        t!("save_as.keep").to_string(),
+                        options.keep_metadata,
+                        |options| options.keep_metadata = true,
+                    ))
+                    .child(self.button(
+                        cx,
+                        "strip_metadata",
+                        t!("save_as.strip").to_string(),
+                        !options.keep_metadata,
+                        |options| options.keep_metadata = false,
+                    )),
+            )
+            .child(t!("save_as.output_size", "width" => width, "height" => height).to_string())
+            .child(
+                div()
+                    .flex()
+                    .justify_end()
+                    .gap_2()
+                    .child(
+                        option_button("cancel", t!("save_as.cancel").to_string(), false).on_click(
+                            cx.listener(|_, _, _window, cx| cx.emit(SaveAsEvent::Cancel)),
+                        ),
+                    )
+                    .child(
+                        option_button("save", t!("save_as.save").to_string(), true).on_click(
+                            cx.listener(move |_, _, _window, cx| {
+                                cx.emit(SaveAsEvent::Save(options));
+                            }),
+                        ),
+                    ),
+            )
+    }
+}
+
+fn row(label: String) -> Div {
+    div()
+        .flex()
+        .items_center()
+        .gap_2()
+        .child(div().w_24().child(label))
+}
+
+fn option_button(
+    id: impl Into<ElementId>,
+    label: impl Into<SharedString>,
+    selected: bool,
+) -> Stateful<Div> {
+    div()
+        .id(id)
+        .cursor_pointer()
+        .px_1()
+        .rounded_sm()
+        .border_1()
+        .border_color(if selected {
+            CONFIG.get().unwrap().theme.primary.into_rgba()
+        } else {
+            CONFIG.get().unwrap().theme.foreground.into_rgba()
+        })
+        .when(!selected, |button| button.opacity(0.6))
+        .child(label.into())
+}
+
+fn step_resize(resize: Resize, increase: bool) -> Resize {
+    match resize {
+        Resize::Original => Resize::Original,
+        Resize::Percentage(percentage) => Resize::Percentage(if increase {
+            (percentage + PERCENTAGE_STEP).min(MAX_PERCENTAGE)
+        } else {
+            percentage
+                .saturating_sub(PERCENTAGE_STEP)
+                .max(PERCENTAGE_STEP)
+        }),
+        Resize::MaxDimension(max) => Resize::MaxDimension(if increase {
+            max + MAX_DIMENSION_STEP
+        } else {
+            max.saturating_sub(MAX_DIMENSION_STEP)
+                .max(MAX_DIMENSION_STEP)
+        }),
+    }
+}
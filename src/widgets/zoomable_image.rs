@@ -1,21 +1,170 @@
-use gpui::*;
-use std::path::PathBuf;
+use gpui::{prelude::FluentBuilder, *};
+use image::{
+    RgbaImage,
+    imageops::{self, FilterType},
+};
+use std::{cell::Cell, mem, rc::Rc, sync::Arc, time::Duration};
 
 use crate::{
-    config::CONFIG,
-    image_info::{self, ImageInfo},
+    adjustments::Adjustments,
+    channels::ChannelView,
+    config::{Backdrop, CONFIG, Color, Sampling},
+    crop::Crop,
+    decoder::{self, DecodeOptions, DecodedFrame, DecodedImage, into_render_image},
+    histogram::Histogram,
+    image_entry::ImageEntry,
+    image_info::{ImageInfo, ImageResolution},
+    widgets::{backdrop::with_backdrop, pixel_grid::pixel_grid},
 };
 
-pub struct ZoomableImage {
-    image: Option<(PathBuf, ImageInfo)>,
+const MIN_PLAYBACK_SPEED: f32 = 0.125;
+const MAX_PLAYBACK_SPEED: f32 = 8.0;
+
+/// Vector images are re-rasterized once their displayed size didn't change for this long,
+/// to avoid rasterizing them for every single zoom step.
+const SVG_RASTER_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Maximum number of pixels of all frames of an image together after upscaling them for
+/// nearest-neighbour sampling, i.e. 64 MiB of RGBA data, see [Config::sampling].
+///
+/// [Config::sampling]: crate::config::Config::sampling
+const MAX_NEAREST_UPSCALED_PIXELS: u64 = 4096 * 4096;
+
+/// Playback state of animated images.
+#[derive(Clone, Copy, Debug)]
+pub struct Playback {
+    pub frame_index: usize,
+    pub playing: bool,
+    pub speed: f32,
+    pub looping: bool,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            frame_index: 0,
+            playing: true,
+            speed: 1.0,
+            looping: !CONFIG.get().unwrap().no_animation_loop,
+        }
+    }
+}
+
+enum LoadState {
+    Loading,
+    Decoded(DecodedImage),
+    /// The image couldn't be decoded, hence gpui's image loader is used as fallback.
+    Failed,
+}
+
+/// The second image of a two-page spread, which is always shown without animation.
+struct SpreadPage {
+    image: ImageEntry,
+    resolution: Option<ImageResolution>,
+    load_state: LoadState,
+    _load_task: Task<()>,
+}
+
+/// A pixel of the current image, inspected by hovering it.
+#[derive(Clone, Copy, Debug)]
+pub struct InspectedPixel {
+    /// Position of the pixel in the image.
+    pub x: u32,
+    pub y: u32,
+    pub color: Color,
+}
+
+/// Zoom, pan and display adjustments of a [ZoomableImage], which can be shared between
+/// multiple images to keep them in sync, e.g. when comparing them.
+pub struct ViewState {
     zoom_factor: f32,
     manual_offset: Point<Pixels>,
+    adjustments: Adjustments,
+    channel: ChannelView,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        Self {
+            zoom_factor: 1.0,
+            manual_offset: Point::default(),
+            adjustments: Adjustments::default(),
+            channel: ChannelView::default(),
+        }
+    }
+}
+
+pub struct ZoomableImage {
+    image: Option<(ImageEntry, ImageInfo)>,
+    load_state: LoadState,
+    /// Histogram of the first frame and of the spread page, computed after decoding them.
+    histogram: Option<Arc<Histogram>>,
+    /// Shown instead of the current image, e.g. the difference to a compared image.
+    substitute: Option<Arc<RenderImage>>,
+    /// All frames with the adjustments and the channel of the view applied, and upscaled for
+    /// nearest-neighbour sampling, or empty if the frames are shown as decoded.
+    ///
+    /// The frames of animations are processed all at once, such that playback continues
+    /// with the adjustments applied.
+    display_frames: Vec<Arc<RenderImage>>,
+    /// The first frame, adjustments, channel and upscale factor [Self::display_frames] are
+    /// (or will soon be) computed from.
+    display_key: Option<(ImageId, Adjustments, ChannelView, u32)>,
+    spread_page: Option<SpreadPage>,
+    /// Whether the spread page is shown left of the current image instead of right of it.
+    right_to_left: bool,
+    backdrop: Backdrop,
+    sampling: Sampling,
+    /// Whether the pixel grid is shown when zoomed in far enough.
+    pixel_grid: bool,
+    /// Whether the pixel under the cursor is inspected.
+    pixel_inspector: bool,
+    inspected_pixel: Option<InspectedPixel>,
+    /// The crop rectangle that is being selected, if cropping.
+    crop: Option<Crop>,
+    /// Where the current image was painted last, for mapping the cursor position to a pixel.
+    image_bounds: Rc<Cell<Option<Bounds<Pixels>>>>,
+    /// The page of the current image that is shown, only relevant for multi-page documents.
+    page: usize,
+    /// Whether images are converted from their color profile to the display profile.
+    color_management: bool,
+    /// Whether the widget is hidden, e.g. by the continuous scroll column, in which case
+    /// images aren't decoded until it's shown again.
+    hidden: bool,
+    playback: Playback,
+    view: Entity<ViewState>,
     move_offset_px: Pixels,
+    /// The width in pixels the current vector image is (or will soon be) rasterized at.
+    svg_raster_width: u32,
+    _load_task: Option<Task<()>>,
+    _info_task: Option<Task<()>>,
+    _histogram_task: Option<Task<()>>,
+    _playback_task: Option<Task<()>>,
+    _svg_raster_task: Option<Task<()>>,
+    _display_task: Option<Task<()>>,
 }
 
 impl Render for ZoomableImage {
-    fn render(&mut self, window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        if let Some(image) = &self.image {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        self.update_svg_raster(window, cx);
+        self.update_display_frames(window, cx);
+
+        if let Some((image, _)) = &self.image {
+            let source = self
+                .substitute
+                .clone()
+                .or_else(|| self.display_frames.get(self.playback.frame_index).cloned())
+                .map(ImageSource::from)
+                .or_else(|| image_source(image, &self.load_state, self.playback.frame_index));
+            let spread = self
+                .spread_page
+                .as_ref()
+                .zip(self.calculate_spread_sizes(window, cx));
+            let view = self.view.read(cx);
+            let offset = view.manual_offset * view.zoom_factor;
+            let image_width = self.calculate_image_size(window, cx).width;
+            let pixel_grid_size = self.pixel_grid_size(window, cx);
+
             div()
                 .id("container")
                 .size_full()
@@ -23,22 +172,62 @@ impl Render for ZoomableImage {
                 .justify_center()
                 .flex()
                 .overflow_scroll()
-                .child(
+                .when(self.pixel_inspector, |container| {
+                    container
+                        .cursor(CursorStyle::Crosshair)
+                        .on_mouse_move(cx.listener(Self::inspect_pixel))
+                })
+                .when(self.crop.is_some(), |container| {
+                    container
+                        .cursor(CursorStyle::Crosshair)
+                        .on_mouse_down(MouseButton::Left, cx.listener(Self::start_crop_drag))
+                        .on_mouse_move(cx.listener(Self::crop_drag))
+                        .on_mouse_up(
+                            MouseButton::Left,
+                            cx.listener(|this, _event, _window, cx| {
+                                if let Some(crop) = &mut this.crop {
+                                    crop.end_drag();
+                                    cx.notify();
+                                }
+                            }),
+                        )
+                })
+                .children(source.map(|source| {
                     div()
                         .id("image_wrapper")
-                        .left(self.manual_offset.x * self.zoom_factor)
-                        .top(self.manual_offset.y * self.zoom_factor)
-                        .child(
-                            img(image.0.clone())
-                                .w(self.calculate_image_size(window).width)
-                                .with_fallback(|| {
-                                    div()
-                                        .text_color(CONFIG.get().unwrap().theme.error.into_rgba())
-                                        .child("failed to load image")
-                                        .into_any_element()
-                                }),
-                        ),
-                )
+                        .left(offset.x)
+                        .top(offset.y)
+                        .map(|wrapper| match spread {
+                            Some((spread_page, (size, spread_size))) => {
+                                let spread_image = div().w(spread_size.width).children(
+                                    image_source(&spread_page.image, &spread_page.load_state, 0)
+                                        .map(|source| {
+                                            with_backdrop(
+                                                image_element(source, spread_size.width),
+                                                self.backdrop,
+                                            )
+                                        }),
+                                );
+                                let image = self.inspectable_image_element(
+                                    source,
+                                    size.width,
+                                    pixel_grid_size,
+                                );
+
+                                let pages = if self.right_to_left {
+                                    [spread_image.into_any_element(), image.into_any_element()]
+                                } else {
+                                    [image.into_any_element(), spread_image.into_any_element()]
+                                };
+                                wrapper.flex().items_center().children(pages)
+                            }
+                            None => wrapper.child(self.inspectable_image_element(
+                                source,
+                                image_width,
+                                pixel_grid_size,
+                            )),
+                        })
+                }))
         } else {
             div()
                 .id("container")
@@ -53,68 +242,993 @@ impl Render for ZoomableImage {
 }
 
 impl ZoomableImage {
-    pub fn new(image_path: Option<PathBuf>) -> Self {
-        Self {
-            image: image_path.map(|image_path| {
-                let info = image_info::ImageInfo::from_file_path(&image_path);
+    pub fn new(cx: &mut Context<Self>, image: Option<ImageEntry>, view: Entity<ViewState>) -> Self {
+        cx.observe(&view, |_, _, cx| cx.notify()).detach();
 
-                (image_path, info)
-            }),
-            zoom_factor: 1.0,
-            manual_offset: Point::default(),
+        let mut this = Self {
+            image: None,
+            load_state: LoadState::Loading,
+            histogram: None,
+            substitute: None,
+            display_frames: Vec::new(),
+            display_key: None,
+            spread_page: None,
+            right_to_left: false,
+            backdrop: CONFIG.get().unwrap().theme.backdrop,
+            sampling: CONFIG.get().unwrap().sampling,
+            pixel_grid: true,
+            pixel_inspector: false,
+            inspected_pixel: None,
+            crop: None,
+            image_bounds: Rc::default(),
+            page: 0,
+            color_management: !CONFIG.get().unwrap().no_color_management,
+            hidden: false,
+            playback: Playback::default(),
+            view,
             move_offset_px: px(40.0),
+            svg_raster_width: 0,
+            _load_task: None,
+            _info_task: None,
+            _histogram_task: None,
+            _playback_task: None,
+            _svg_raster_task: None,
+            _display_task: None,
+        };
+        this.set_image(cx, image);
+
+        this
+    }
+
+    pub fn set_image(&mut self, cx: &mut Context<Self>, image: Option<ImageEntry>) {
+        self.image = image.map(|image| {
+            let info = match &image {
+                // reading archive entries means decompressing them, see Self::load_image_info
+                ImageEntry::ArchiveEntry { archive, name } => {
+                    ImageInfo::from_archive_entry_name(archive, name)
+                }
+                ImageEntry::File(_) | ImageEntry::Pasted(_) => ImageInfo::from_entry(&image),
+            };
+
+            (image, info)
+        });
+        self.load_image_info(cx);
+        self.page = 0;
+        self.crop = None;
+
+        self.load_image(cx);
+    }
+
+    /// Complete the info of the current image in the background, if it's an archive entry.
+    fn load_image_info(&mut self, cx: &mut Context<Self>) {
+        self._info_task = self
+            .image
+            .as_ref()
+            .filter(|(image, _)| matches!(image, ImageEntry::ArchiveEntry { .. }))
+            .map(|(image, _)| {
+                let image = image.clone();
+
+                cx.spawn(async move |this, cx| {
+                    let task_image = image.clone();
+                    let info = cx
+                        .background_spawn(async move { ImageInfo::from_entry(&task_image) })
+                        .await;
+
+                    let _ = this.update(cx, |this, cx| {
+                        let Some((_, current_info)) =
+                            this.image.as_mut().filter(|(current, _)| *current == image)
+                        else {
+                            return;
+                        };
+
+                        // keep what is known from decoding the image already
+                        current_info.file_type = info.file_type;
+                        current_info.file_size = info.file_size;
+                        current_info.resolution = current_info.resolution.or(info.resolution);
+
+                        cx.notify();
+                    });
+                })
+            });
+    }
+
+    /// Show `image` next to the current image as a two-page spread,
+    /// or only the current image if `None`.
+    pub fn set_spread_page(
+        &mut self,
+        cx: &mut Context<Self>,
+        image: Option<ImageEntry>,
+        right_to_left: bool,
+    ) {
+        self.right_to_left = right_to_left;
+        cx.notify();
+
+        if self.spread_page.as_ref().map(|page| &page.image) == image.as_ref() {
+            return;
         }
+
+        self.drop_spread_render_images(cx);
+        self.spread_page = image.map(|image| {
+            // archive entries are measured once decoded, instead of decompressing them twice
+            let resolution = match &image {
+                ImageEntry::ArchiveEntry { .. } => None,
+                ImageEntry::File(_) | ImageEntry::Pasted(_) => {
+                    ImageInfo::from_entry(&image).resolution
+                }
+            };
+
+            let options = DecodeOptions {
+                page: 0,
+                max_svg_raster_size: CONFIG.get().unwrap().max_svg_raster_size,
+                color_management: self.color_management,
+                first_frame_only: false,
+            };
+            let task_image = image.clone();
+            let load_task = cx.spawn(async move |this, cx| {
+                let decoded = cx
+                    .background_spawn(async move { decoder::decode(&task_image, options) })
+                    .await;
+
+                let _ = this.update(cx, |this, cx| {
+                    if let Some(spread_page) = &mut this.spread_page {
+                        spread_page.set_decoded_image(decoded.ok());
+                    }
+                    this.update_histogram(cx);
+
+                    cx.notify();
+                });
+            });
+
+            SpreadPage {
+                image,
+                resolution,
+                load_state: LoadState::Loading,
+                _load_task: load_task,
+            }
+        });
+        self.update_histogram(cx);
     }
 
-    pub fn set_image(&mut self, cx: &mut Context<Self>, image_path: Option<PathBuf>) {
-        self.image = image_path.map(|image_path| {
-            let info = image_info::ImageInfo::from_file_path(&image_path);
+    /// Decode the current page of the current image in the background.
+    fn load_image(&mut self, cx: &mut Context<Self>) {
+        self.drop_render_images(cx);
+        self.load_state = LoadState::Loading;
+        self.histogram = None;
+        self._histogram_task = None;
+        self.inspected_pixel = None;
+        self.playback = Playback::default();
+        self._playback_task = None;
+        self._svg_raster_task = None;
+        self.svg_raster_width = 0;
+
+        if self.hidden {
+            self._load_task = None;
+            cx.notify();
+            return;
+        }
+
+        let options = DecodeOptions {
+            page: self.page,
+            max_svg_raster_size: CONFIG.get().unwrap().max_svg_raster_size,
+            color_management: self.color_management,
+            first_frame_only: false,
+        };
+        self._load_task = self.image.as_ref().map(|(image, _)| {
+            let image = image.clone();
 
-            (image_path, info)
+            cx.spawn(async move |this, cx| {
+                let decoded = cx
+                    .background_spawn(async move { decoder::decode(&image, options) })
+                    .await;
+
+                let _ = this.update(cx, |this, cx| this.set_decoded_image(cx, decoded.ok()));
+            })
         });
 
         cx.notify();
     }
 
-    pub fn zoom_in(&mut self, cx: &mut Context<Self>) {
-        self.zoom_factor *= 1.2;
+    fn set_decoded_image(&mut self, cx: &mut Context<Self>, decoded: Option<DecodedImage>) {
+        let Some(decoded) = decoded else {
+            self.load_state = LoadState::Failed;
+            cx.notify();
+            return;
+        };
+
+        if let Some((_, info)) = &mut self.image {
+            info.frame_count = Some(decoded.frames.len());
+            info.duration = decoded.is_animated().then(|| decoded.duration());
+
+            if let Some((width, height)) = decoded.svg_size() {
+                info.file_type.get_or_insert_with(|| "SVG".to_string());
+                info.resolution = Some(ImageResolution::from_size(width, height));
+            } else if let Some(frame) = decoded.frames.first()
+                && (info.resolution.is_none() || decoded.page_count > 1)
+            {
+                // pages of a document don't necessarily have the same size
+                let (width, height) = frame.size();
+                info.resolution = Some(ImageResolution {
+                    width: width as usize,
+                    height: height as usize,
+                });
+            }
+            info.page_count = Some(decoded.page_count);
+            info.color_profile.clone_from(&decoded.color_profile);
+            info.color_managed = self.color_management && decoded.color_error.is_none();
+            info.color_error.clone_from(&decoded.color_error);
+        }
+        self.svg_raster_width = decoded.frames.first().map_or(0, |frame| frame.size().0);
+
+        self.load_state = LoadState::Decoded(decoded);
+        self.schedule_next_frame(cx);
+        self.update_histogram(cx);
 
         cx.notify();
     }
 
-    pub fn zoom_out(&mut self, cx: &mut Context<Self>) {
-        self.zoom_factor *= 0.8;
+    /// Compute the histogram of the first frame of the current image and of the spread page
+    /// in the background, e.g. after either was decoded again with(out) color management.
+    fn update_histogram(&mut self, cx: &mut Context<Self>) {
+        let spread_page = self
+            .spread_page
+            .as_ref()
+            .and_then(|page| match &page.load_state {
+                LoadState::Decoded(decoded) => Some(decoded),
+                _ => None,
+            });
+        let frames: Vec<Arc<RenderImage>> = [self.decoded_image(), spread_page]
+            .into_iter()
+            .flatten()
+            .filter_map(|decoded| decoded.frames.first())
+            .map(|frame| frame.render_image.clone())
+            .collect();
+
+        self.histogram = None;
+        self._histogram_task = (!frames.is_empty()).then(|| {
+            cx.spawn(async move |this, cx| {
+                let histogram = cx
+                    .background_spawn(async move {
+                        let buffers: Vec<RgbaImage> =
+                            frames.iter().map(|frame| decoder::to_rgba(frame)).collect();
+                        Histogram::from_images(&buffers)
+                    })
+                    .await;
+
+                let _ = this.update(cx, |this, cx| {
+                    this.histogram = Some(Arc::new(histogram));
+                    cx.notify();
+                });
+            })
+        });
+    }
+
+    /// Re-rasterize the current vector image (debounced) if its displayed size changed.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn update_svg_raster(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(tree) = self.decoded_image().and_then(|decoded| decoded.svg.clone()) else {
+            return;
+        };
+
+        let displayed_width =
+            f32::from(self.calculate_image_size(window, cx).width) * window.scale_factor();
+        let width = decoder::clamp_svg_raster_width(
+            &tree,
+            displayed_width.ceil() as u32,
+            CONFIG.get().unwrap().max_svg_raster_size,
+        );
+        if width == self.svg_raster_width {
+            return;
+        }
+        self.svg_raster_width = width;
+
+        self._svg_raster_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(SVG_RASTER_DEBOUNCE).await;
+
+            let raster = cx
+                .background_spawn(async move { decoder::rasterize_svg(&tree, width) })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                let LoadState::Decoded(decoded) = &mut this.load_state else {
+                    return;
+                };
+
+                if let Ok(raster) = raster
+                    && let Some(previous_frame) = decoded.replace_svg_raster(raster)
+                {
+                    cx.drop_image(previous_frame.render_image, None);
+                }
+
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Apply the adjustments and the channel of the view to all frames and upscale them for
+    /// nearest-neighbour sampling in the background, if any of them or the frames changed.
+    fn update_display_frames(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let ViewState {
+            adjustments,
+            channel,
+            ..
+        } = *self.view.read(cx);
+        let upscale = self.nearest_upscale_factor(window, cx);
+        let key = self
+            .decoded_image()
+            .and_then(|decoded| decoded.frames.first())
+            .map(|frame| frame.render_image.id)
+            .filter(|_| !adjustments.is_identity() || channel != ChannelView::All || upscale > 1)
+            .map(|id| (id, adjustments, channel, upscale));
+        if key == self.display_key {
+            return;
+        }
+        self.display_key = key;
+
+        let frames: Option<Vec<Arc<RenderImage>>> = self
+            .decoded_image()
+            .filter(|_| key.is_some())
+            .map(|decoded| {
+                decoded
+                    .frames
+                    .iter()
+                    .map(|frame| frame.render_image.clone())
+                    .collect()
+            });
+        let Some(frames) = frames else {
+            // this is called while rendering, hence there's no need to notify
+            for display_frame in self.display_frames.drain(..) {
+                cx.drop_image(display_frame, None);
+            }
+            self._display_task = None;
+            return;
+        };
+
+        // keep showing the previous frames until the new ones are ready
+        self._display_task = Some(cx.spawn(async move |this, cx| {
+            let display_frames = cx
+                .background_spawn(async move {
+                    frames
+                        .iter()
+                        .map(|frame| {
+                            let frame = decoder::to_rgba(frame);
+                            let frame = channel.apply(&adjustments.apply(&frame));
+                            if upscale > 1 {
+                                into_render_image(imageops::resize(
+                                    &frame,
+                                    frame.width() * upscale,
+                                    frame.height() * upscale,
+                                    FilterType::Nearest,
+                                ))
+                            } else {
+                                into_render_image(frame)
+                            }
+                        })
+                        .collect()
+                })
+                .await;
+
+            let _ = this.update(cx, |this, cx| {
+                for previous in mem::replace(&mut this.display_frames, display_frames) {
+                    cx.drop_image(previous, None);
+                }
+
+                cx.notify();
+            });
+        }));
+    }
+
+    /// How many screen pixels (in logical pixels) an image pixel spans.
+    #[allow(clippy::cast_precision_loss)]
+    fn magnification(&self, window: &mut Window, cx: &App) -> Option<f32> {
+        let (width, _) = self.image_size().filter(|(width, _)| *width > 0)?;
+
+        Some(f32::from(self.calculate_image_size(window, cx).width) / width as f32)
+    }
+
+    /// The factor the current frame is upscaled by, such that gpui's smooth sampling only
+    /// blurs the edges between the image pixels slightly, or 1 for smooth sampling.
+    ///
+    /// The factor is limited by [MAX_NEAREST_UPSCALED_PIXELS], hence large images are always
+    /// sampled smoothly. It's a power of two to avoid upscaling the frame again on every zoom
+    /// step.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn nearest_upscale_factor(&self, window: &mut Window, cx: &App) -> u32 {
+        // vector images are rasterized at the displayed size anyway
+        let Some(decoded) = self.decoded_image().filter(|decoded| decoded.svg.is_none()) else {
+            return 1;
+        };
+        let Some(frame) = self.current_frame() else {
+            return 1;
+        };
+        let Some(magnification) = self.magnification(window, cx) else {
+            return 1;
+        };
+
+        let nearest = match self.sampling {
+            Sampling::Smooth => false,
+            Sampling::Nearest => true,
+            Sampling::Auto => magnification >= CONFIG.get().unwrap().nearest_sampling_threshold,
+        };
+        // the frame might be smaller than the image, e.g. for RAW previews
+        let displayed_width =
+            f32::from(self.calculate_image_size(window, cx).width) * window.scale_factor();
+        let device_magnification = displayed_width / frame.size().0.max(1) as f32;
+        if !nearest || device_magnification <= 1.0 {
+            return 1;
+        }
+
+        // all frames of animations are upscaled
+        let pixels =
+            u64::from(frame.width()) * u64::from(frame.height()) * decoded.frames.len() as u64;
+        let mut factor = (device_magnification.ceil() as u32).next_power_of_two();
+        while factor > 1
+            && pixels.saturating_mul(u64::from(factor).pow(2)) > MAX_NEAREST_UPSCALED_PIXELS
+        {
+            factor /= 2;
+        }
+
+        factor
+    }
+
+    /// The size of the image if the pixel grid is shown on top of it.
+    fn pixel_grid_size(&self, window: &mut Window, cx: &App) -> Option<(u32, u32)> {
+        if !self.pixel_grid
+            || self
+                .decoded_image()
+                .is_none_or(|decoded| decoded.svg.is_some())
+        {
+            return None;
+        }
+
+        self.magnification(window, cx)
+            .filter(|magnification| *magnification >= CONFIG.get().unwrap().pixel_grid_threshold)
+            .and(self.image_size())
+    }
+
+    /// Remove the frames of the current image from gpui's image cache.
+    fn drop_render_images(&mut self, cx: &mut Context<Self>) {
+        if let LoadState::Decoded(decoded) = &self.load_state {
+            for frame in &decoded.frames {
+                cx.drop_image(frame.render_image.clone(), None);
+            }
+        }
+        for display_frame in self.display_frames.drain(..) {
+            cx.drop_image(display_frame, None);
+        }
+        self.display_key = None;
+        self._display_task = None;
+    }
+
+    fn drop_spread_render_images(&mut self, cx: &mut Context<Self>) {
+        if let Some(SpreadPage {
+            load_state: LoadState::Decoded(decoded),
+            ..
+        }) = &self.spread_page
+        {
+            for frame in &decoded.frames {
+                cx.drop_image(frame.render_image.clone(), None);
+            }
+        }
+    }
+
+    fn decoded_image(&self) -> Option<&DecodedImage> {
+        match &self.load_state {
+            LoadState::Decoded(decoded) => Some(decoded),
+            _ => None,
+        }
+    }
+
+    /// The current image.
+    pub fn image(&self) -> Option<&ImageEntry> {
+        self.image.as_ref().map(|(image, _)| image)
+    }
+
+    /// The [ImageInfo] of the current image, including information that is only
+    /// known after decoding (e.g. the number of frames).
+    pub fn image_info(&self) -> Option<ImageInfo> {
+        self.image.as_ref().map(|(_, info)| info.clone())
+    }
+
+    /// Hide or show the widget, decoding the current image once it's shown again.
+    pub fn set_hidden(&mut self, hidden: bool, cx: &mut Context<Self>) {
+        self.hidden = hidden;
+
+        if !hidden && self.image.is_some() && self._load_task.is_none() {
+            self.load_image(cx);
+        }
+    }
+
+    pub fn color_management(&self) -> bool {
+        self.color_management
+    }
+
+    /// Reload the images with or without color management, e.g. to compare both.
+    pub fn toggle_color_management(&mut self, cx: &mut Context<Self>) {
+        self.color_management = !self.color_management;
+
+        // keep showing the same frame of animations
+        let playback = self.playback;
+        self.load_image(cx);
+        self.playback = playback;
+
+        let spread_page = self.spread_page.as_ref().map(|page| page.image.clone());
+        self.drop_spread_render_images(cx);
+        self.spread_page = None;
+        self.set_spread_page(cx, spread_page, self.right_to_left);
+    }
+
+    pub fn backdrop(&self) -> Backdrop {
+        self.backdrop
+    }
+
+    pub fn cycle_backdrop(&mut self, cx: &mut Context<Self>) {
+        self.backdrop = self.backdrop.next();
 
         cx.notify();
     }
 
-    pub fn move_up(&mut self, cx: &mut Context<Self>) {
-        self.manual_offset += Point::new(px(0.0), self.move_offset_px);
+    pub fn cycle_sampling(&mut self, cx: &mut Context<Self>) {
+        self.sampling = self.sampling.next();
 
         cx.notify();
     }
 
-    pub fn move_down(&mut self, cx: &mut Context<Self>) {
-        self.manual_offset += Point::new(px(0.0), -self.move_offset_px);
+    pub fn toggle_pixel_grid(&mut self, cx: &mut Context<Self>) {
+        self.pixel_grid = !self.pixel_grid;
 
         cx.notify();
     }
 
-    pub fn move_left(&mut self, cx: &mut Context<Self>) {
-        self.manual_offset += Point::new(self.move_offset_px, px(0.0));
+    pub fn toggle_pixel_inspector(&mut self, cx: &mut Context<Self>) {
+        self.pixel_inspector = !self.pixel_inspector;
+        self.inspected_pixel = None;
 
         cx.notify();
     }
 
-    pub fn move_right(&mut self, cx: &mut Context<Self>) {
-        self.manual_offset += Point::new(-self.move_offset_px, px(0.0));
+    /// Start or stop selecting a part of the current image to crop it to.
+    pub fn toggle_crop(&mut self, cx: &mut Context<Self>) {
+        if self.crop.take().is_none() {
+            self.crop = self.image_size().map(Crop::new);
+        }
 
         cx.notify();
     }
 
-    fn calculate_image_size(&self, window: &mut Window) -> Size<Pixels> {
+    pub fn crop(&self) -> Option<&Crop> {
+        self.crop.as_ref()
+    }
+
+    pub fn cycle_crop_aspect_ratio(&mut self, cx: &mut Context<Self>) {
+        if let Some(crop) = &mut self.crop {
+            crop.cycle_aspect_ratio();
+            cx.notify();
+        }
+    }
+
+    pub fn toggle_crop_preview(&mut self, cx: &mut Context<Self>) {
+        if let Some(crop) = &mut self.crop {
+            crop.preview = !crop.preview;
+            cx.notify();
+        }
+    }
+
+    /// Type into the fields of the crop rectangle, returning whether the key was used.
+    pub fn type_crop_key(&mut self, cx: &mut Context<Self>, key: &str) -> bool {
+        let typed = self.crop.as_mut().is_some_and(|crop| crop.type_key(key));
+        if typed {
+            cx.notify();
+        }
+
+        typed
+    }
+
+    /// The pixels of the shown frame, cropped to the crop rectangle if one is selected.
+    pub fn cropped_frame(&self) -> Option<RgbaImage> {
+        let frame = decoder::to_rgba(&self.current_frame()?.render_image);
+
+        self.crop
+            .as_ref()
+            .and_then(|crop| crop.apply(&frame))
+            .or(Some(frame))
+    }
+
+    /// The size of [Self::cropped_frame], without cropping the frame.
+    pub fn cropped_size(&self) -> Option<(u32, u32)> {
+        let frame_size = self.current_frame()?.size();
+
+        self.crop
+            .as_ref()
+            .and_then(|crop| crop.cropped_size(frame_size))
+            .or(Some(frame_size))
+    }
+
+    /// The size of the current image in pixels, which might differ from the size of its
+    /// frames, e.g. for rasterized vector images.
+    #[allow(clippy::cast_possible_truncation)]
+    fn image_size(&self) -> Option<(u32, u32)> {
+        self.image
+            .as_ref()
+            .and_then(|(_, info)| info.resolution)
+            .map(|resolution| (resolution.width as u32, resolution.height as u32))
+            .or_else(|| self.current_frame().map(DecodedFrame::size))
+    }
+
+    /// The position in image pixels that `position` in the window maps to, clamped to the
+    /// edges of the image. This relies on where the image was painted last, hence takes
+    /// zooming and panning into account.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn position_in_image(
+        &self,
+        position: Point<Pixels>,
+        (width, height): (u32, u32),
+    ) -> Option<(u32, u32)> {
+        let bounds = self.image_bounds.get()?;
+        let position = position - bounds.origin;
+        let to_pixel = |position: Pixels, size: Pixels, pixels: u32| {
+            ((position / size).clamp(0.0, 1.0) * pixels as f32).round() as u32
+        };
+
+        Some((
+            to_pixel(position.x, bounds.size.width, width),
+            to_pixel(position.y, bounds.size.height, height),
+        ))
+    }
+
+    fn start_crop_drag(
+        &mut self,
+        event: &MouseDownEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // clicking next to the image keeps the current selection
+        if !self
+            .image_bounds
+            .get()
+            .is_some_and(|bounds| bounds.contains(&event.position))
+        {
+            return;
+        }
+
+        let Some(image_size) = self.crop.as_ref().map(Crop::image_size) else {
+            return;
+        };
+        let position = self.position_in_image(event.position, image_size);
+        if let Some((crop, position)) = self.crop.as_mut().zip(position) {
+            crop.start_drag(position);
+            cx.notify();
+        }
+    }
+
+    fn crop_drag(&mut self, event: &MouseMoveEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(image_size) = self
+            .crop
+            .as_ref()
+            .filter(|crop| crop.is_dragging())
+            .map(Crop::image_size)
+        else {
+            return;
+        };
+        let position = self.position_in_image(event.position, image_size);
+        let Some(crop) = &mut self.crop else {
+            return;
+        };
+
+        if event.pressed_button == Some(MouseButton::Left) {
+            if let Some(position) = position {
+                crop.drag(position);
+            }
+        } else {
+            // the button was released outside of the window
+            crop.end_drag();
+        }
+
+        cx.notify();
+    }
+
+    pub fn inspected_pixel(&self) -> Option<InspectedPixel> {
+        self.inspected_pixel
+    }
+
+    /// Look up the pixel under the cursor.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn inspect_pixel(
+        &mut self,
+        event: &MouseMoveEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let pixel = self
+            .image_bounds
+            .get()
+            .filter(|bounds| bounds.contains(&event.position))
+            .zip(self.current_frame())
+            .and_then(|(bounds, frame)| {
+                let position = event.position - bounds.origin;
+                let relative_x = f32::from(position.x) / f32::from(bounds.size.width);
+                let relative_y = f32::from(position.y) / f32::from(bounds.size.height);
+
+                // the frame might be smaller than the image, e.g. for rasterized vector images
+                let (width, height) = self
+                    .image
+                    .as_ref()
+                    .and_then(|(_, info)| info.resolution)
+                    .map_or(frame.size(), |resolution| {
+                        (resolution.width as u32, resolution.height as u32)
+                    });
+                let to_pixel = |relative: f32, size: u32| {
+                    ((relative * size as f32) as u32).min(size.saturating_sub(1))
+                };
+
+                let (frame_width, frame_height) = frame.size();
+                let [r, g, b, a] = frame
+                    .pixel(
+                        to_pixel(relative_x, frame_width),
+                        to_pixel(relative_y, frame_height),
+                    )?
+                    .0;
+
+                Some(InspectedPixel {
+                    x: to_pixel(relative_x, width),
+                    y: to_pixel(relative_y, height),
+                    color: Color::new(r, g, b, a),
+                })
+            });
+
+        self.inspected_pixel = pixel;
+        cx.notify();
+    }
+
+    pub fn histogram(&self) -> Option<Arc<Histogram>> {
+        self.histogram.clone()
+    }
+
+    /// The frame that is currently shown.
+    pub fn current_frame(&self) -> Option<&DecodedFrame> {
+        self.decoded_image()
+            .and_then(|decoded| decoded.frames.get(self.playback.frame_index))
+    }
+
+    /// The id of the frame that is currently shown, which changes whenever its pixels change.
+    pub fn current_frame_id(&self) -> Option<ImageId> {
+        self.decoded_image()
+            .and_then(|decoded| decoded.frames.get(self.playback.frame_index))
+            .map(|frame| frame.render_image.id)
+    }
+
+    /// Show `image` instead of the current image, or the current image again if `None`.
+    pub fn set_substitute(&mut self, cx: &mut Context<Self>, image: Option<Arc<RenderImage>>) {
+        if let Some(previous) = self.substitute.take() {
+            cx.drop_image(previous, None);
+        }
+        self.substitute = image;
+
+        cx.notify();
+    }
+
+    /// The current page and the number of pages, only available for multi-page documents.
+    pub fn page(&self) -> Option<(usize, usize)> {
+        self.decoded_image()
+            .filter(|decoded| decoded.page_count > 1)
+            .map(|decoded| (self.page, decoded.page_count))
+    }
+
+    /// Go `offset` pages forward (or backward if negative).
+    pub fn change_page(&mut self, cx: &mut Context<Self>, offset: isize) {
+        let Some((page, page_count)) = self.page() else {
+            return;
+        };
+
+        let new_page = page.saturating_add_signed(offset).min(page_count - 1);
+        if new_page == page {
+            return;
+        }
+
+        self.page = new_page;
+        // pages of a document don't necessarily have the same size
+        self.crop = None;
+        self.load_image(cx);
+    }
+
+    /// The playback state, only available for animated images.
+    pub fn playback(&self) -> Option<Playback> {
+        self.decoded_image()
+            .filter(|decoded| decoded.is_animated())
+            .map(|_| self.playback)
+    }
+
+    /// Show the next frame after the delay of the current frame has passed.
+    fn schedule_next_frame(&mut self, cx: &mut Context<Self>) {
+        self._playback_task = None;
+
+        if !self.playback.playing {
+            return;
+        }
+
+        let Some(delay) = self
+            .decoded_image()
+            .filter(|decoded| decoded.is_animated())
+            .and_then(|decoded| decoded.frames.get(self.playback.frame_index))
+            .map(|frame| frame.delay.div_f32(self.playback.speed))
+        else {
+            return;
+        };
+
+        self._playback_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(delay).await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.advance_frame(cx);
+            });
+        }));
+    }
+
+    fn advance_frame(&mut self, cx: &mut Context<Self>) {
+        let frame_count = self
+            .decoded_image()
+            .map_or(0, |decoded| decoded.frames.len());
+
+        if self.playback.frame_index + 1 < frame_count {
+            self.playback.frame_index += 1;
+        } else if self.playback.looping {
+            self.playback.frame_index = 0;
+        } else {
+            self.playback.playing = false;
+        }
+
+        self.schedule_next_frame(cx);
+        cx.notify();
+    }
+
+    pub fn toggle_playback(&mut self, cx: &mut Context<Self>) {
+        let Some(frame_count) = self
+            .decoded_image()
+            .filter(|decoded| decoded.is_animated())
+            .map(|decoded| decoded.frames.len())
+        else {
+            return;
+        };
+
+        self.playback.playing = !self.playback.playing;
+        // play non-looping animations again from the start once they ended
+        if self.playback.playing
+            && !self.playback.looping
+            && self.playback.frame_index + 1 >= frame_count
+        {
+            self.playback.frame_index = 0;
+        }
+        self.schedule_next_frame(cx);
+
+        cx.notify();
+    }
+
+    /// Pause the animation and step `offset` frames forward (or backward if negative).
+    pub fn step_frame(&mut self, cx: &mut Context<Self>, offset: isize) {
+        let Some(frame_count) = self
+            .decoded_image()
+            .filter(|decoded| decoded.is_animated())
+            .map(|decoded| decoded.frames.len())
+        else {
+            return;
+        };
+
+        self.playback.playing = false;
+        self.playback.frame_index = self
+            .playback
+            .frame_index
+            .saturating_add_signed(offset)
+            .min(frame_count - 1);
+        self.schedule_next_frame(cx);
+
+        cx.notify();
+    }
+
+    /// Multiply the playback speed by the given factor.
+    pub fn change_playback_speed(&mut self, cx: &mut Context<Self>, factor: f32) {
+        self.playback.speed =
+            (self.playback.speed * factor).clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+        self.schedule_next_frame(cx);
+
+        cx.notify();
+    }
+
+    pub fn toggle_looping(&mut self, cx: &mut Context<Self>) {
+        self.playback.looping = !self.playback.looping;
+
+        // restart the animation if it already stopped at the last frame
+        let frame_count = self
+            .decoded_image()
+            .map_or(0, |decoded| decoded.frames.len());
+        if self.playback.looping
+            && !self.playback.playing
+            && self.playback.frame_index + 1 == frame_count
+        {
+            self.playback.playing = true;
+            self.schedule_next_frame(cx);
+        }
+
+        cx.notify();
+    }
+
+    /// Change the zoom or the offset, which also affects all images sharing the view.
+    fn update_view(&self, cx: &mut Context<Self>, update: impl FnOnce(&mut ViewState)) {
+        self.view.update(cx, |view, cx| {
+            update(view);
+            cx.notify();
+        });
+    }
+
+    pub fn adjustments(&self, cx: &App) -> Adjustments {
+        self.view.read(cx).adjustments
+    }
+
+    /// Change the adjustments of the view, which only affect how the images are shown.
+    pub fn adjust(&mut self, cx: &mut Context<Self>, adjust: impl FnOnce(&mut Adjustments)) {
+        self.update_view(cx, |view| adjust(&mut view.adjustments));
+    }
+
+    pub fn channel(&self, cx: &App) -> ChannelView {
+        self.view.read(cx).channel
+    }
+
+    /// Show only `channel`, or all channels again if it is already shown.
+    pub fn toggle_channel(&mut self, cx: &mut Context<Self>, channel: ChannelView) {
+        self.update_view(cx, |view| {
+            view.channel = if view.channel == channel {
+                ChannelView::All
+            } else {
+                channel
+            };
+        });
+    }
+
+    pub fn zoom_in(&mut self, cx: &mut Context<Self>) {
+        self.update_view(cx, |view| view.zoom_factor *= 1.2);
+    }
+
+    pub fn zoom_out(&mut self, cx: &mut Context<Self>) {
+        self.update_view(cx, |view| view.zoom_factor *= 0.8);
+    }
+
+    pub fn move_up(&mut self, cx: &mut Context<Self>) {
+        let offset = Point::new(px(0.0), self.move_offset_px);
+        self.update_view(cx, |view| view.manual_offset += offset);
+    }
+
+    pub fn move_down(&mut self, cx: &mut Context<Self>) {
+        let offset = Point::new(px(0.0), -self.move_offset_px);
+        self.update_view(cx, |view| view.manual_offset += offset);
+    }
+
+    pub fn move_left(&mut self, cx: &mut Context<Self>) {
+        let offset = Point::new(self.move_offset_px, px(0.0));
+        self.update_view(cx, |view| view.manual_offset += offset);
+    }
+
+    pub fn move_right(&mut self, cx: &mut Context<Self>) {
+        let offset = Point::new(-self.move_offset_px, px(0.0));
+        self.update_view(cx, |view| view.manual_offset += offset);
+    }
+
+    fn calculate_image_size(&self, window: &mut Window, cx: &App) -> Size<Pixels> {
+        if let Some((size, _)) = self.calculate_spread_sizes(window, cx) {
+            return size;
+        }
+
         let window_size = window.bounds().size;
-        let width = window_size.width / 2.0 * self.zoom_factor;
+        let width = window_size.width / 2.0 * self.view.read(cx).zoom_factor;
 
         if let Some(image_resolution) = self.image.as_ref().and_then(|(_, info)| info.resolution) {
             let height = width / image_resolution.aspect_ratio();
@@ -123,4 +1237,173 @@ impl ZoomableImage {
             size(width, Pixels::default())
         }
     }
+
+    /// The image element of the current image, which records its bounds for the pixel inspector.
+    ///
+    /// The pixel grid of an image of `pixel_grid_size` is laid over it, if given.
+    fn inspectable_image_element(
+        &self,
+        source: ImageSource,
+        width: Pixels,
+        pixel_grid_size: Option<(u32, u32)>,
+    ) -> Div {
+        let image_bounds = self.image_bounds.clone();
+
+        with_backdrop(image_element(source, width), self.backdrop)
+            .child(
+                canvas(
+                    move |bounds, _window, _cx| image_bounds.set(Some(bounds)),
+                    |_bounds, (), _window, _cx| {},
+                )
+                .absolute()
+                .size_full(),
+            )
+            .children(pixel_grid_size.map(pixel_grid))
+            .children(self.crop.as_ref().and_then(crop_overlay))
+    }
+
+    /// The sizes of the current image and the spread page, scaled to the same height
+    /// such that the whole spread fits into the window.
+    fn calculate_spread_sizes(
+        &self,
+        window: &mut Window,
+        cx: &App,
+    ) -> Option<(Size<Pixels>, Size<Pixels>)> {
+        let spread_page = self.spread_page.as_ref()?;
+
+        let aspect_ratio = self
+            .image
+            .as_ref()
+            .and_then(|(_, info)| info.resolution)
+            .map(|resolution| resolution.aspect_ratio());
+        let spread_aspect_ratio = spread_page
+            .resolution
+            .map(|resolution| resolution.aspect_ratio());
+        // pages of unknown size are assumed to have the same size as the other page
+        let (aspect_ratio, spread_aspect_ratio) = match (aspect_ratio, spread_aspect_ratio) {
+            (Some(aspect_ratio), Some(spread_aspect_ratio)) => (aspect_ratio, spread_aspect_ratio),
+            (Some(aspect_ratio), None) | (None, Some(aspect_ratio)) => (aspect_ratio, aspect_ratio),
+            (None, None) => return None,
+        };
+
+        let window_size = window.bounds().size;
+        let height = f32::from(window_size.height)
+            .min(f32::from(window_size.width) / (aspect_ratio + spread_aspect_ratio))
+            * self.view.read(cx).zoom_factor;
+
+        Some((
+            size(px(height * aspect_ratio), px(height)),
+            size(px(height * spread_aspect_ratio), px(height)),
+        ))
+    }
+}
+
+impl SpreadPage {
+    fn set_decoded_image(&mut self, decoded: Option<DecodedImage>) {
+        let Some(decoded) = decoded else {
+            self.load_state = LoadState::Failed;
+            return;
+        };
+
+        if let Some((width, height)) = decoded.svg_size() {
+            self.resolution = Some(ImageResolution::from_size(width, height));
+        } else if let Some(frame) = decoded.frames.first() {
+            let (width, height) = frame.size();
+            self.resolution.get_or_insert(ImageResolution {
+                width: width as usize,
+                height: height as usize,
+            });
+        }
+
+        self.load_state = LoadState::Decoded(decoded);
+    }
+}
+
+/// The source of the given frame of the image, if it's already loaded.
+fn image_source(
+    image: &ImageEntry,
+    load_state: &LoadState,
+    frame_index: usize,
+) -> Option<ImageSource> {
+    match load_state {
+        LoadState::Loading => None,
+        LoadState::Decoded(decoded) => decoded
+            .frames
+            .get(frame_index)
+            .map(|frame| frame.render_image.clone().into()),
+        // let gpui try to load the file itself, e.g. if it was changed in the meantime
+        LoadState::Failed => match image {
+            ImageEntry::File(path) => Some(path.clone().into()),
+            ImageEntry::ArchiveEntry { .. } | ImageEntry::Pasted(_) => None,
+        },
+    }
+}
+
+fn image_element(source: ImageSource, width: Pixels) -> Img {
+    img(source).w(width).with_fallback(|| {
+        div()
+            .text_color(CONFIG.get().unwrap().theme.error.into_rgba())
+            .child("failed to load image")
+            .into_any_element()
+    })
+}
+
+/// Shades the parts of the image outside of the crop rectangle, or hides them in the preview.
+#[allow(clippy::cast_precision_loss)]
+fn crop_overlay(crop: &Crop) -> Option<Div> {
+    let rect = crop.rect?;
+    let (width, height) = crop.image_size();
+    let left = rect.x as f32 / width as f32;
+    let top = rect.y as f32 / height as f32;
+    let right = (rect.x + rect.width) as f32 / width as f32;
+    let bottom = (rect.y + rect.height) as f32 / height as f32;
+
+    let shade = if crop.preview {
+        Hsla::from(CONFIG.get().unwrap().theme.background.into_rgba())
+    } else {
+        black().opacity(0.6)
+    };
+    let shaded = || div().absolute().bg(shade);
+
+    Some(
+        div()
+            .absolute()
+            .top_0()
+            .left_0()
+            .size_full()
+            .child(shaded().top_0().left_0().w_full().h(relative(top)))
+            .child(
+                shaded()
+                    .bottom_0()
+                    .left_0()
+                    .w_full()
+                    .h(relative(1.0 - bottom)),
+            )
+            .child(
+                shaded()
+                    .top(relative(top))
+                    .h(relative(bottom - top))
+                    .left_0()
+                    .w(relative(left)),
+            )
+            .child(
+                shaded()
+                    .top(relative(top))
+                    .h(relative(bottom - top))
+                    .right_0()
+                    .w(relative(1.0 - right)),
+            )
+            .when(!crop.preview, |overlay| {
+                overlay.child(
+                    div()
+                        .absolute()
+                        .top(relative(top))
+                        .left(relative(left))
+                        .w(relative(right - left))
+                        .h(relative(bottom - top))
+                        .border_1()
+                        .border_color(CONFIG.get().unwrap().theme.primary.into_rgba()),
+                )
+            }),
+    )
 }
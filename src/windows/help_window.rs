@@ -89,12 +89,23 @@ impl Render for KeyBindingItem {
                             .join("-"),
                     ),
             )
-            .child(
-                t!(format!(
-                    "actions.{}",
-                    binding.action().name().to_lowercase()
-                ))
-                .to_string(),
-            )
+            .child(action_description(binding.action()))
     }
 }
+
+/// The translated description of the action, including the command line for [RunCommand]s.
+fn action_description(action: &dyn Action) -> String {
+    if let Some(RunCommand { index }) = action.as_any().downcast_ref::<RunCommand>() {
+        let command = CONFIG
+            .get()
+            .unwrap()
+            .commands
+            .get(*index)
+            .map(|command| command.command.as_str())
+            .unwrap_or_default();
+
+        return t!("actions.runcommand", "command" => command).to_string();
+    }
+
+    t!(format!("actions.{}", action.name().to_lowercase())).to_string()
+}
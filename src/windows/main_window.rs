@@ -1,23 +1,145 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeSet, HashSet},
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    process::exit,
+    sync::Arc,
+    time::Duration,
+};
 
 use crate::{
     actions::*,
-    config::CONFIG,
-    widgets::{image_info::ImageInfoWidget, zoomable_image::ZoomableImage},
+    adjustments::{Adjustment, Adjustments},
+    channels::ChannelView,
+    clipboard,
+    commands::{self, CommandContext},
+    config::{CONFIG, resolve_image_paths},
+    decoder::{self, PreviewImage, into_render_image},
+    diff::{self, DiffMetrics},
+    export::{self, ExportFormat, ExportOptions},
+    image_entry::{ImageEntry, PastedImage},
+    image_info::ImageInfo,
+    ipc,
+    widgets::{
+        adjustments::AdjustmentsWidget,
+        backdrop::with_backdrop,
+        crop::CropWidget,
+        diff_metrics::DiffMetricsWidget,
+        histogram::{HistogramSettings, HistogramWidget},
+        image_info::ImageInfoWidget,
+        pixel_inspector::PixelInspectorWidget,
+        save_as::{SaveAsDialog, SaveAsEvent},
+        zoomable_image::{ViewState, ZoomableImage},
+    },
     windows::help_window::HelpWindow,
 };
 use gpui::{prelude::FluentBuilder, *};
 
+/// Exit code used in picker mode if the window is closed without picking an image.
+pub const PICK_CANCELLED_EXIT_CODE: i32 = 2;
+
+/// Events emitted by the [AppWindow], e.g. to notify clients of the [crate::ipc] socket.
+pub enum AppEvent {
+    /// The shown image or the list of images changed.
+    ImageChanged,
+}
+
+impl EventEmitter<AppEvent> for AppWindow {}
+
+/// How many images before and after the visible ones keep their previews in continuous
+/// scroll mode, as the list renders a bit beyond the visible range.
+const PREVIEW_MARGIN: usize = 4;
+
+/// How two images are shown when comparing them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CompareMode {
+    /// Next to each other, each taking half of the window.
+    SideBySide,
+    /// On top of each other, the selected image left and the compared image right of a
+    /// draggable split line.
+    Split,
+    /// The compared image semi-transparent on top of the selected image, like onion skin.
+    Blend,
+    /// Alternating between both images.
+    Flicker,
+    /// The absolute difference of both images, black where they're equal.
+    Difference,
+    /// The difference multiplied by [crate::config::Config::difference_amplification].
+    AmplifiedDifference,
+}
+
+impl CompareMode {
+    fn next(self) -> Self {
+        match self {
+            Self::SideBySide => Self::Split,
+            Self::Split => Self::Blend,
+            Self::Blend => Self::Flicker,
+            Self::Flicker => Self::Difference,
+            Self::Difference => Self::AmplifiedDifference,
+            Self::AmplifiedDifference => Self::SideBySide,
+        }
+    }
+
+    /// The factor the differences are multiplied with, if the difference is shown.
+    fn amplification(self) -> Option<f32> {
+        match self {
+            Self::Difference => Some(1.0),
+            Self::AmplifiedDifference => Some(CONFIG.get().unwrap().difference_amplification),
+            _ => None,
+        }
+    }
+}
+
 pub struct AppWindow {
     focus_handle: FocusHandle,
-    image_paths: Vec<PathBuf>,
+    images: Vec<ImageEntry>,
+    /// The files and directories the images were opened from, resolved again when reloading.
+    sources: Vec<PathBuf>,
     selected_img_index: usize,
     zoomable_image: Entity<ZoomableImage>,
     show_image_info: bool,
+    marked_img_indices: BTreeSet<usize>,
+    pick_mode: bool,
+    command_error: Option<String>,
+    /// Whether two consecutive images are shown side by side.
+    spread: bool,
+    right_to_left: bool,
+    /// Whether all images are stacked in a vertically scrollable column.
+    continuous_scroll: bool,
+    scroll_list: ListState,
+    /// The images whose previews were loaded for the continuous scroll column, together
+    /// with whether they were color managed.
+    preview_images: HashSet<(ImageEntry, bool)>,
+    show_histogram: bool,
+    histogram_settings: HistogramSettings,
+    /// The image compared with the selected image, sharing its zoom and offset.
+    compare_image: Entity<ZoomableImage>,
+    compare: bool,
+    compare_mode: CompareMode,
+    /// The position of the split line as fraction of the window width.
+    split_position: f32,
+    dragging_split: bool,
+    /// The opacity of the compared image in blend mode.
+    blend_opacity: f32,
+    /// Whether the compared image instead of the selected image is shown in flicker mode.
+    flicker_shows_compared: bool,
+    _flicker_task: Option<Task<()>>,
+    /// The metrics of the compared frames, or why they couldn't be computed.
+    diff_metrics: Option<Result<DiffMetrics, String>>,
+    /// The frames the metrics were computed for.
+    diff_key: Option<(ImageId, ImageId)>,
+    /// The frames and the amplification the difference image was computed for.
+    difference_key: Option<(ImageId, ImageId, f32)>,
+    _diff_task: Option<Task<()>>,
+    _difference_task: Option<Task<()>>,
+    save_as_dialog: Option<Entity<SaveAsDialog>>,
+    /// The options chosen the last time an image was saved via the "save as" dialog.
+    export_options: ExportOptions,
 }
 
 impl Render for AppWindow {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         div()
             .bg(if CONFIG.get().unwrap().no_transparency {
                 CONFIG.get().unwrap().theme.background.into_rgba()
@@ -26,7 +148,7 @@ impl Render for AppWindow {
             })
             .text_color(CONFIG.get().unwrap().theme.foreground.into_rgba())
             .track_focus(&self.focus_handle)
-            .on_action(|_: &CloseWindow, window, _cx| window.remove_window())
+            .on_action(cx.listener(Self::close_window))
             .on_action(cx.listener(Self::open_files))
             .on_action(cx.listener(Self::open_help))
             .on_action(cx.listener(Self::toggle_fullscreen))
@@ -41,55 +163,539 @@ impl Render for AppWindow {
             .on_action(cx.listener(Self::move_left))
             .on_action(cx.listener(Self::move_right))
             .on_action(cx.listener(Self::toggle_image_info))
+            .on_action(cx.listener(Self::toggle_mark))
+            .on_action(cx.listener(Self::pick))
+            .on_action(cx.listener(Self::run_command))
+            .on_action(cx.listener(Self::toggle_playback))
+            .on_action(cx.listener(Self::next_frame))
+            .on_action(cx.listener(Self::prev_frame))
+            .on_action(cx.listener(Self::increase_playback_speed))
+            .on_action(cx.listener(Self::decrease_playback_speed))
+            .on_action(cx.listener(Self::toggle_animation_loop))
+            .on_action(cx.listener(Self::next_page))
+            .on_action(cx.listener(Self::prev_page))
+            .on_action(cx.listener(Self::toggle_spread))
+            .on_action(cx.listener(Self::toggle_reading_direction))
+            .on_action(cx.listener(Self::toggle_continuous_scroll))
+            .on_action(cx.listener(Self::copy_image))
+            .on_action(cx.listener(Self::copy_path))
+            .on_action(cx.listener(Self::paste_image))
+            .on_action(cx.listener(Self::save_image))
+            .on_action(cx.listener(Self::toggle_color_management))
+            .on_action(cx.listener(Self::toggle_histogram))
+            .on_action(cx.listener(Self::toggle_histogram_scale))
+            .on_action(cx.listener(Self::toggle_histogram_channel))
+            .on_action(cx.listener(Self::toggle_pixel_inspector))
+            .on_action(cx.listener(Self::copy_color))
+            .on_action(cx.listener(Self::cycle_backdrop))
+            .on_action(cx.listener(Self::toggle_compare))
+            .on_action(cx.listener(Self::cycle_compare_mode))
+            .on_action(cx.listener(Self::increase_blend_opacity))
+            .on_action(cx.listener(Self::decrease_blend_opacity))
+            .on_action(cx.listener(Self::toggle_crop))
+            .on_action(cx.listener(Self::cycle_crop_aspect_ratio))
+            .on_action(cx.listener(Self::toggle_crop_preview))
+            .on_action(cx.listener(Self::save_as))
+            .on_action(cx.listener(Self::increase_exposure))
+            .on_action(cx.listener(Self::decrease_exposure))
+            .on_action(cx.listener(Self::increase_contrast))
+            .on_action(cx.listener(Self::decrease_contrast))
+            .on_action(cx.listener(Self::increase_gamma))
+            .on_action(cx.listener(Self::decrease_gamma))
+            .on_action(cx.listener(Self::increase_saturation))
+            .on_action(cx.listener(Self::decrease_saturation))
+            .on_action(cx.listener(Self::reset_adjustments))
+            .on_action(cx.listener(Self::show_red_channel))
+            .on_action(cx.listener(Self::show_green_channel))
+            .on_action(cx.listener(Self::show_blue_channel))
+            .on_action(cx.listener(Self::show_alpha_channel))
+            .on_action(cx.listener(Self::show_alpha_mask))
+            .on_action(cx.listener(Self::show_premultiplied_alpha))
+            .on_action(cx.listener(Self::cycle_sampling))
+            .on_action(cx.listener(Self::toggle_pixel_grid))
+            .on_key_down(cx.listener(Self::type_crop_key))
+            .on_mouse_move(cx.listener(Self::drag_split))
+            .on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|this, _event, _window, _cx| this.dragging_split = false),
+            )
+            .on_drop(cx.listener(Self::drop_files))
+            .drag_over::<ExternalPaths>(|style, _, _window, _cx| {
+                style
+                    .border_4()
+                    .border_color(CONFIG.get().unwrap().theme.primary.into_rgba())
+            })
             .size_full()
             .relative()
-            .child(
-                div()
-                    .size_full()
-                    .absolute()
-                    .child(self.zoomable_image.clone()),
-            )
+            .child(div().size_full().absolute().map(|container| {
+                if self.continuous_scroll {
+                    container.child(
+                        list(
+                            self.scroll_list.clone(),
+                            cx.processor(Self::render_scroll_item),
+                        )
+                        .size_full(),
+                    )
+                } else if self.compare {
+                    container.child(self.render_compare(window, cx))
+                } else {
+                    container.child(self.zoomable_image.clone())
+                }
+            }))
             .when_some(
-                self.selected_image().take_if(|_| self.show_image_info),
-                |container, image_path| {
+                self.image_info(cx).take_if(|_| self.show_image_info),
+                |container, image_info| {
+                    let playback = self.zoomable_image.read(cx).playback();
+                    let channel = self.zoomable_image.read(cx).channel(cx);
+
                     container.child(
                         div()
                             .absolute()
                             .top_2()
                             .right_2()
-                            .child(cx.new(|_| ImageInfoWidget::new(image_path))),
+                            .child(cx.new(|_| ImageInfoWidget::new(image_info, playback, channel))),
                     )
                 },
             )
+            .child(
+                div()
+                    .absolute()
+                    .top_2()
+                    .left_2()
+                    .flex()
+                    .flex_col()
+                    .gap_2()
+                    .when_some(
+                        self.zoomable_image
+                            .read(cx)
+                            .histogram()
+                            .take_if(|_| self.show_histogram),
+                        |container, histogram| {
+                            let settings = self.histogram_settings;
+
+                            container.child(cx.new(|_| HistogramWidget::new(histogram, settings)))
+                        },
+                    )
+                    .when_some(
+                        self.zoomable_image.read(cx).inspected_pixel(),
+                        |container, pixel| {
+                            container.child(cx.new(|_| PixelInspectorWidget::new(pixel)))
+                        },
+                    )
+                    .when_some(
+                        self.zoomable_image.read(cx).crop().cloned(),
+                        |container, crop| container.child(cx.new(|_| CropWidget::new(crop))),
+                    )
+                    .when_some(
+                        Some(self.zoomable_image.read(cx).adjustments(cx))
+                            .filter(|adjustments| !adjustments.is_identity()),
+                        |container, adjustments| {
+                            container.child(cx.new(|_| AdjustmentsWidget::new(adjustments)))
+                        },
+                    )
+                    .when_some(
+                        self.diff_metrics.clone().take_if(|_| self.compare),
+                        |container, metrics| {
+                            container.child(cx.new(|_| DiffMetricsWidget::new(metrics)))
+                        },
+                    ),
+            )
+            .when_some(self.save_as_dialog.clone(), |container, dialog| {
+                container.child(
+                    div()
+                        .absolute()
+                        .size_full()
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .child(dialog),
+                )
+            })
+            .when_some(self.command_error.clone(), |container, error| {
+                container.child(
+                    div()
+                        .absolute()
+                        .bottom_2()
+                        .left_2()
+                        .max_w_1_2()
+                        .px_2()
+                        .rounded_md()
+                        .bg(CONFIG.get().unwrap().theme.surface.into_rgba())
+                        .text_color(CONFIG.get().unwrap().theme.error.into_rgba())
+                        .child(t!("status.command_failed", "error" => error).to_string()),
+                )
+            })
             .when_some(self.selected_image(), |container, _| {
-                container.child(div().absolute().bottom_2().right_2().child(format!(
-                    "{}/{}",
-                    self.selected_img_index + 1,
-                    self.image_paths.len()
-                )))
+                container.child(
+                    div()
+                        .absolute()
+                        .bottom_2()
+                        .right_2()
+                        .flex()
+                        .gap_2()
+                        .when(!self.marked_img_indices.is_empty(), |div| {
+                            div.child(
+                                t!("status.marked", "count" => self.marked_img_indices.len())
+                                    .to_string(),
+                            )
+                        })
+                        .when_some(self.zoomable_image.read(cx).page(), |div, (page, count)| {
+                            div.child(
+                                t!("status.page", "page" => page + 1, "count" => count).to_string(),
+                            )
+                        })
+                        .child(
+                            div()
+                                .when(
+                                    self.marked_img_indices.contains(&self.selected_img_index),
+                                    |div| {
+                                        div.text_color(
+                                            CONFIG.get().unwrap().theme.primary.into_rgba(),
+                                        )
+                                    },
+                                )
+                                .child(if self.spread_page().is_some() {
+                                    format!(
+                                        "{}-{}/{}",
+                                        self.selected_img_index + 1,
+                                        self.selected_img_index + 2,
+                                        self.images.len()
+                                    )
+                                } else {
+                                    format!("{}/{}", self.selected_img_index + 1, self.images.len())
+                                }),
+                        ),
+                )
             })
     }
 }
 
 impl AppWindow {
-    pub fn new(window: &mut Window, cx: &mut App, image_paths: Vec<PathBuf>) -> Self {
+    pub fn new(
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        sources: Vec<PathBuf>,
+        pick_mode: bool,
+    ) -> Self {
         window.set_window_title(env!("CARGO_PKG_NAME"));
 
         let focus_handle = cx.focus_handle();
         focus_handle.focus(window, cx);
 
-        let image = image_paths.first().cloned();
-        Self {
+        if pick_mode {
+            // closing the window via the compositor counts as cancelling the selection
+            window.on_window_should_close(cx, |_window, _cx| {
+                ipc::remove_socket();
+                exit(PICK_CANCELLED_EXIT_CODE)
+            });
+        }
+
+        let view = cx.new(|_| ViewState::default());
+        let continuous_scroll = CONFIG.get().unwrap().continuous_scroll;
+        let zoomable_image = cx.new(|cx| {
+            let mut zoomable_image = ZoomableImage::new(cx, None, view.clone());
+            zoomable_image.set_hidden(continuous_scroll, cx);
+
+            zoomable_image
+        });
+        let compare_image = cx.new(|cx| ZoomableImage::new(cx, None, view));
+        // e.g. to update the current frame in the image info, or the difference of both images
+        for image in [&zoomable_image, &compare_image] {
+            cx.observe(image, |this, _, cx| {
+                this.update_difference(cx);
+                cx.notify();
+            })
+            .detach();
+        }
+
+        let scroll_list = ListState::new(0, ListAlignment::Top, px(1000.0));
+        let this_handle = cx.weak_entity();
+        scroll_list.set_scroll_handler(move |event, _window, cx| {
+            let _ = this_handle.update(cx, |this, cx| {
+                this.scrolled_to_image(event.visible_range.start, cx);
+
+                let start = event.visible_range.start.saturating_sub(PREVIEW_MARGIN);
+                this.evict_previews(start..event.visible_range.end + PREVIEW_MARGIN, cx);
+            });
+        });
+
+        let mut this = Self {
             focus_handle,
-            image_paths,
+            images: Vec::new(),
+            // kept even if they contain no images yet, see [Self::reload_images]
+            sources: sources.clone(),
             selected_img_index: 0,
-            zoomable_image: cx.new(|_| ZoomableImage::new(image)),
+            zoomable_image,
             show_image_info: true,
+            marked_img_indices: BTreeSet::new(),
+            pick_mode,
+            command_error: None,
+            spread: CONFIG.get().unwrap().spread,
+            right_to_left: CONFIG.get().unwrap().right_to_left,
+            continuous_scroll,
+            scroll_list,
+            preview_images: HashSet::new(),
+            show_histogram: false,
+            histogram_settings: HistogramSettings::default(),
+            compare_image,
+            compare: false,
+            compare_mode: CompareMode::SideBySide,
+            split_position: 0.5,
+            dragging_split: false,
+            blend_opacity: 0.5,
+            flicker_shows_compared: false,
+            _flicker_task: None,
+            diff_metrics: None,
+            diff_key: None,
+            difference_key: None,
+            _diff_task: None,
+            _difference_task: None,
+            save_as_dialog: None,
+            export_options: ExportOptions::default(),
+        };
+        this.update_spread_page(cx);
+        this.open_paths(sources, false, cx);
+
+        this
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_img_index
+    }
+
+    pub fn image_count(&self) -> usize {
+        self.images.len()
+    }
+
+    /// The [ImageInfo] of the selected image.
+    pub fn image_info(&self, cx: &App) -> Option<ImageInfo> {
+        self.zoomable_image.read(cx).image_info()
+    }
+
+    pub fn selected_image(&self) -> Option<ImageEntry> {
+        self.images.get(self.selected_img_index).cloned()
+    }
+
+    /// The index of the first image of the spread that contains the image at `index`,
+    /// or `index` itself if spreads are disabled.
+    fn spread_start(&self, index: usize) -> usize {
+        if !self.spread || self.continuous_scroll || self.compare {
+            return index;
+        }
+
+        if CONFIG.get().unwrap().single_cover {
+            // the cover is shown on its own, hence spreads start at odd indices
+            if index == 0 {
+                0
+            } else {
+                index - (index - 1) % 2
+            }
+        } else {
+            index - index % 2
+        }
+    }
+
+    /// The image shown next to the selected image if spreads are enabled.
+    fn spread_page(&self) -> Option<ImageEntry> {
+        if !self.spread
+            || self.continuous_scroll
+            || self.compare
+            || (CONFIG.get().unwrap().single_cover && self.selected_img_index == 0)
+        {
+            return None;
+        }
+
+        self.images.get(self.selected_img_index + 1).cloned()
+    }
+
+    /// The image compared with the selected image: the first other marked image,
+    /// or the next image if no other image is marked.
+    fn compare_target(&self) -> Option<ImageEntry> {
+        if !self.compare || self.images.len() < 2 {
+            return None;
+        }
+
+        let index = self
+            .marked_img_indices
+            .iter()
+            .copied()
+            .find(|index| *index != self.selected_img_index)
+            .unwrap_or((self.selected_img_index + 1) % self.images.len());
+
+        self.images.get(index).cloned()
+    }
+
+    fn update_compare_image(&mut self, cx: &mut Context<Self>) {
+        let image = self.compare_target();
+        if self.compare_image.read(cx).image() == image.as_ref() {
+            return;
+        }
+
+        self.compare_image.update(cx, |compare_image, cx| {
+            compare_image.set_image(cx, image);
+        });
+    }
+
+    /// The marked images, or the selected image if no image is marked.
+    fn marked_or_selected_images(&self) -> Vec<ImageEntry> {
+        if self.marked_img_indices.is_empty() {
+            return self.selected_image().into_iter().collect();
+        }
+
+        self.marked_img_indices
+            .iter()
+            .filter_map(|index| self.images.get(*index).cloned())
+            .collect()
+    }
+
+    fn close_window(
+        &mut self,
+        _action: &CloseWindow,
+        window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        if self.pick_mode {
+            ipc::remove_socket();
+            exit(PICK_CANCELLED_EXIT_CODE);
+        }
+
+        window.remove_window();
+    }
+
+    fn toggle_mark(&mut self, _action: &ToggleMark, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.selected_image().is_none() {
+            return;
+        }
+
+        if !self.marked_img_indices.remove(&self.selected_img_index) {
+            self.marked_img_indices.insert(self.selected_img_index);
         }
+        self.update_compare_image(cx);
+
+        cx.notify();
+    }
+
+    /// Print the picked image paths to stdout and exit, only has an effect in picker mode.
+    fn pick(&mut self, _action: &Pick, _window: &mut Window, _cx: &mut Context<Self>) {
+        if !self.pick_mode {
+            return;
+        }
+
+        let picked_images = self.marked_or_selected_images();
+        if picked_images.is_empty() {
+            return;
+        }
+
+        let mut stdout = io::stdout().lock();
+        for image in picked_images {
+            let _ = writeln!(stdout, "{image}");
+        }
+        let _ = stdout.flush();
+
+        ipc::remove_socket();
+        exit(0);
+    }
+
+    fn run_command(&mut self, action: &RunCommand, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(command) = CONFIG.get().unwrap().commands.get(action.index) else {
+            return;
+        };
+
+        let current_image = self.selected_image();
+        // commands operate on files, so archive entries are passed as their archive
+        let mut marked_images: Vec<PathBuf> = Vec::new();
+        for image in self.marked_or_selected_images() {
+            if let Some(path) = image.path()
+                && !marked_images.iter().any(|marked_path| marked_path == path)
+            {
+                marked_images.push(path.to_path_buf());
+            }
+        }
+        let command_line = commands::expand_placeholders(
+            &command.command,
+            &CommandContext {
+                current_image: current_image.as_ref().and_then(ImageEntry::path),
+                marked_images: &marked_images,
+                index: self.selected_img_index,
+            },
+        );
+        let reload = command.reload;
+
+        self.command_error = None;
+        cx.notify();
+
+        let task = cx.background_spawn(async move { commands::run(&command_line) });
+        cx.spawn(async move |window, cx| {
+            let result = task.await;
+
+            let _ = window.update(cx, |this, cx| {
+                match result {
+                    Ok(()) if reload => this.reload_images(cx),
+                    Ok(()) => {}
+                    Err(err) => this.command_error = Some(err.to_string()),
+                }
+
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Resolve the opened files and directories again and reload the current image,
+    /// e.g. after a [RunCommand] created, modified or deleted files.
+    ///
+    /// Images that don't exist anymore are removed, new ones are added in the order of their
+    /// sources, and pasted images are kept at the end.
+    fn reload_images(&mut self, cx: &mut Context<Self>) {
+        let sources = self.sources.clone();
+
+        cx.spawn(async move |this, cx| {
+            let images = cx
+                .background_spawn(
+                    async move { ImageEntry::from_paths(resolve_image_paths(&sources)) },
+                )
+                .await;
+
+            let _ = this.update(cx, |this, cx| this.set_reloaded_images(images, cx));
+        })
+        .detach();
     }
 
-    fn selected_image(&self) -> Option<PathBuf> {
-        self.image_paths.get(self.selected_img_index).cloned()
+    /// Replace the list of images with the ones resolved by [Self::reload_images], keeping
+    /// the current and the marked images selected.
+    fn set_reloaded_images(&mut self, mut images: Vec<ImageEntry>, cx: &mut Context<Self>) {
+        let selected_image = self.selected_image();
+        let marked_images: Vec<_> = self
+            .marked_img_indices
+            .iter()
+            .filter_map(|index| self.images.get(*index).cloned())
+            .collect();
+
+        // e.g. images opened via the file dialog that a command moved into a new directory
+        for image in &self.images {
+            if image.path().is_none_or(Path::is_file) && !images.contains(image) {
+                images.push(image.clone());
+            }
+        }
+        self.images = images;
+        self.scroll_list.reset(self.images.len());
+
+        self.marked_img_indices = self
+            .images
+            .iter()
+            .enumerate()
+            .filter(|(_, image)| marked_images.contains(image))
+            .map(|(index, _)| index)
+            .collect();
+        self.selected_img_index = selected_image
+            .and_then(|selected| self.images.iter().position(|image| *image == selected))
+            .unwrap_or_else(|| {
+                self.selected_img_index
+                    .min(self.images.len().saturating_sub(1))
+            });
+        self.selected_img_index = self.spread_start(self.selected_img_index);
+
+        self.set_image(cx, self.selected_image());
     }
 
     fn prev_image(
@@ -102,16 +708,18 @@ impl AppWindow {
             return;
         }
 
-        self.selected_img_index -= 1;
+        self.selected_img_index = self.spread_start(self.selected_img_index - 1);
         self.set_image(cx, self.selected_image());
     }
 
     fn next_image(&mut self, _action: &NextImage, _window: &mut Window, cx: &mut Context<Self>) {
-        if self.selected_img_index + 1 >= self.image_paths.len() {
+        // skip the spread page, as it is already shown
+        let step = if self.spread_page().is_some() { 2 } else { 1 };
+        if self.selected_img_index + step >= self.images.len() {
             return;
         }
 
-        self.selected_img_index += 1;
+        self.selected_img_index += step;
         self.set_image(cx, self.selected_image());
     }
 
@@ -121,7 +729,7 @@ impl AppWindow {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if self.image_paths.is_empty() {
+        if self.images.is_empty() {
             return;
         }
 
@@ -135,104 +743,684 @@ impl AppWindow {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if self.image_paths.is_empty() {
+        if self.images.is_empty() {
             return;
         }
 
-        self.selected_img_index = self.image_paths.len() - 1;
+        self.selected_img_index = self.spread_start(self.images.len() - 1);
         self.set_image(cx, self.selected_image());
     }
 
-    fn set_image(&mut self, cx: &mut Context<Self>, image: Option<PathBuf>) {
+    fn set_image(&mut self, cx: &mut Context<Self>, image: Option<ImageEntry>) {
+        // the error belongs to the image the command was run for
+        self.command_error = None;
         self.zoomable_image.update(cx, |zoomable_image, cx| {
             zoomable_image.set_image(cx, image);
         });
+        self.update_spread_page(cx);
+        self.update_compare_image(cx);
+        self.scroll_to_selected_image();
+
+        cx.emit(AppEvent::ImageChanged);
     }
 
-    #[allow(clippy::unused_self)]
-    fn open_help(&mut self, _action: &Help, _window: &mut Window, cx: &mut Context<Self>) {
-        let window_bounds =
-            WindowBounds::Windowed(Bounds::centered(None, size(px(300.0), px(300.0)), cx));
+    fn scroll_to_selected_image(&self) {
+        if self.continuous_scroll {
+            self.scroll_list.scroll_to(ListOffset {
+                item_ix: self.selected_img_index,
+                offset_in_item: px(0.0),
+            });
+        }
+    }
 
-        let _ = cx.open_window(
-            WindowOptions {
-                window_bounds: Some(window_bounds),
-                kind: WindowKind::Dialog,
-                ..Default::default()
-            },
-            |window, cx| cx.new(|cx| HelpWindow::new(window, cx)),
-        );
+    /// Select the image at the top of the column in continuous scroll mode.
+    ///
+    /// Unlike [Self::set_image], this doesn't scroll the column, as the user just did.
+    fn scrolled_to_image(&mut self, index: usize, cx: &mut Context<Self>) {
+        if !self.continuous_scroll || index == self.selected_img_index {
+            return;
+        }
+
+        self.selected_img_index = index;
+        self.command_error = None;
+        let image = self.selected_image();
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.set_image(cx, image);
+        });
+
+        cx.emit(AppEvent::ImageChanged);
+        cx.notify();
     }
 
-    #[allow(clippy::unused_self)]
-    fn toggle_fullscreen(
+    fn render_scroll_item(
         &mut self,
-        _action: &ToggleFullscreen,
-        window: &mut Window,
-        _cx: &mut Context<Self>,
-    ) {
-        window.toggle_fullscreen();
-    }
+        index: usize,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let Some(image) = self.images.get(index).cloned() else {
+            return div().into_any_element();
+        };
+        let preview = (image, self.zoomable_image.read(cx).color_management());
+        self.preview_images.insert(preview.clone());
 
-    #[allow(clippy::unused_self)]
-    fn open_files(&mut self, _action: &OpenFiles, _window: &mut Window, cx: &mut Context<Self>) {
-        let recv = cx.prompt_for_paths(PathPromptOptions {
-            files: true,
-            directories: false,
-            multiple: true,
-            prompt: None,
+        let image = img(move |window: &mut Window, cx: &mut App| {
+            window.use_asset::<PreviewImage>(&preview, cx)
+        })
+        .w_full()
+        .with_fallback(|| {
+            div()
+                .text_color(CONFIG.get().unwrap().theme.error.into_rgba())
+                .child("failed to load image")
+                .into_any_element()
         });
 
-        cx.spawn(async |window, cx| {
-            let mut selection_result = recv.await.ok().and_then(std::result::Result::ok).and_then(|res| res);
+        div()
+            .w_full()
+            .child(with_backdrop(
+                image,
+                self.zoomable_image.read(cx).backdrop(),
+            ))
+            .into_any_element()
+    }
 
-            if let Some(images) = &mut selection_result {
-                let _ = window.update(cx, |this, cx| {
-                    let prev_size = this.image_paths.len();
+    /// Remove the previews of all images outside of `kept` from gpui's asset cache, which
+    /// otherwise holds on to every image scrolled past.
+    fn evict_previews(&mut self, kept: Range<usize>, cx: &mut Context<Self>) {
+        let kept = self.images.get(kept.start..kept.end.min(self.images.len()));
+        let kept: HashSet<&ImageEntry> = kept.unwrap_or_default().iter().collect();
 
-                    // append new images and seek to first new image
-                    this.image_paths.append(images);
-                    this.selected_img_index = prev_size;
+        for image in self
+            .preview_images
+            .extract_if(|(image, _)| !kept.contains(image))
+        {
+            PreviewImage::evict(&image, cx);
+        }
+    }
 
-                    this.set_image(cx, this.selected_image());
-                });
+    /// The selected image and the compared image, next to or on top of each other.
+    fn render_compare(&self, window: &mut Window, cx: &mut Context<Self>) -> Div {
+        let theme = &CONFIG.get().unwrap().theme;
+        let label = |image: &Entity<ZoomableImage>| {
+            let name = image.read(cx).image_info()?.file_name?;
+
+            Some(
+                div()
+                    .px_2()
+                    .rounded_md()
+                    .bg(theme.surface.into_rgba())
+                    .child(name),
+            )
+        };
+        let centered = |label: Div| {
+            div()
+                .absolute()
+                .bottom_2()
+                .w_full()
+                .flex()
+                .justify_center()
+                .child(label)
+        };
+
+        match self.compare_mode {
+            CompareMode::SideBySide => {
+                div()
+                    .size_full()
+                    .flex()
+                    .children([&self.zoomable_image, &self.compare_image].map(|image| {
+                        div()
+                            .w_1_2()
+                            .h_full()
+                            .relative()
+                            .child(image.clone())
+                            .children(label(image).map(centered))
+                    }))
             }
-        })
-        .detach();
+            CompareMode::Split => {
+                let width = window.viewport_size().width;
+                let split_x = width * self.split_position;
+
+                div()
+                    .size_full()
+                    .relative()
+                    .child(self.zoomable_image.clone())
+                    .child(
+                        div()
+                            .absolute()
+                            .top_0()
+                            .bottom_0()
+                            .left(split_x)
+                            .right_0()
+                            .overflow_hidden()
+                            // keep the compared image aligned with the selected image below
+                            .child(
+                                div()
+                                    .absolute()
+                                    .top_0()
+                                    .h_full()
+                                    .left(-split_x)
+                                    .w(width)
+                                    .child(self.compare_image.clone()),
+                            ),
+                    )
+                    .child(
+                        // the handle is wider than the line to make it easier to grab
+                        div()
+                            .absolute()
+                            .top_0()
+                            .bottom_0()
+                            .left(split_x - px(4.0))
+                            .w(px(8.0))
+                            .flex()
+                            .justify_center()
+                            .cursor(CursorStyle::ResizeLeftRight)
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _event, _window, _cx| {
+                                    this.dragging_split = true;
+                                }),
+                            )
+                            .child(div().w(px(2.0)).h_full().bg(theme.primary.into_rgba())),
+                    )
+                    .children(label(&self.zoomable_image).map(|label| {
+                        div()
+                            .absolute()
+                            .bottom_2()
+                            .right(width - split_x + px(8.0))
+                            .child(label)
+                    }))
+                    .children(label(&self.compare_image).map(|label| {
+                        div()
+                            .absolute()
+                            .bottom_2()
+                            .left(split_x + px(8.0))
+                            .child(label)
+                    }))
+            }
+            CompareMode::Blend => div()
+                .size_full()
+                .relative()
+                .child(self.zoomable_image.clone())
+                .child(
+                    div()
+                        .absolute()
+                        .size_full()
+                        .opacity(self.blend_opacity)
+                        .child(self.compare_image.clone()),
+                )
+                .child(centered(
+                    div()
+                        .px_2()
+                        .rounded_md()
+                        .bg(theme.surface.into_rgba())
+                        .child(
+                            t!(
+                                "compare.opacity",
+                                "percent" => (self.blend_opacity * 100.0).round()
+                            )
+                            .to_string(),
+                        ),
+                )),
+            CompareMode::Flicker => {
+                let image = if self.flicker_shows_compared {
+                    &self.compare_image
+                } else {
+                    &self.zoomable_image
+                };
+
+                div()
+                    .size_full()
+                    .relative()
+                    .child(image.clone())
+                    .children(label(image).map(centered))
+            }
+            // the difference is shown as substitute of the selected image
+            CompareMode::Difference | CompareMode::AmplifiedDifference => {
+                div().size_full().child(self.zoomable_image.clone())
+            }
+        }
     }
 
-    pub fn zoom_in(&mut self, _action: &ZoomIn, _window: &mut Window, cx: &mut Context<Self>) {
-        self.zoomable_image.update(cx, |zoomable_image, cx| {
-            zoomable_image.zoom_in(cx);
-        });
+    /// Compute the metrics (and the difference image in the difference modes) once both
+    /// compared frames are decoded, and again whenever one of them changes.
+    fn update_difference(&mut self, cx: &mut Context<Self>) {
+        let frame_ids = self
+            .zoomable_image
+            .read(cx)
+            .current_frame_id()
+            .zip(self.compare_image.read(cx).current_frame_id())
+            .filter(|_| self.compare);
+        let difference_key = frame_ids
+            .zip(self.compare_mode.amplification())
+            .map(|((id, compared_id), amplification)| (id, compared_id, amplification));
+
+        if frame_ids != self.diff_key {
+            self.diff_key = frame_ids;
+            self.diff_metrics = None;
+            let frames = frame_ids.and_then(|_| self.compared_frames(cx));
+            self._diff_task = frames.map(|(frame, compared_frame)| {
+                cx.spawn(async move |this, cx| {
+                    let metrics = cx
+                        .background_spawn(async move {
+                            DiffMetrics::compute(
+                                &decoder::to_rgba(&frame),
+                                &decoder::to_rgba(&compared_frame),
+                            )
+                        })
+                        .await;
+
+                    let _ = this.update(cx, |this, cx| {
+                        this.diff_metrics = Some(metrics.map_err(|err| err.to_string()));
+                        cx.notify();
+                    });
+                })
+            });
+        }
+
+        // e.g. switching to the flicker mode keeps the metrics, but not the difference image
+        if difference_key != self.difference_key {
+            self.difference_key = difference_key;
+            if difference_key.is_none() {
+                self.zoomable_image.update(cx, |zoomable_image, cx| {
+                    zoomable_image.set_substitute(cx, None);
+                });
+            }
+
+            let frames = difference_key
+                .and_then(|(_, _, amplification)| Some((amplification, self.compared_frames(cx)?)));
+            self._difference_task = frames.map(|(amplification, (frame, compared_frame))| {
+                cx.spawn(async move |this, cx| {
+                    // differently sized frames are reported by the metrics already
+                    let Ok(difference) = cx
+                        .background_spawn(async move {
+                            diff::difference_image(
+                                &decoder::to_rgba(&frame),
+                                &decoder::to_rgba(&compared_frame),
+                                amplification,
+                            )
+                            .map(into_render_image)
+                        })
+                        .await
+                    else {
+                        return;
+                    };
+
+                    let _ = this.update(cx, |this, cx| {
+                        this.zoomable_image.update(cx, |zoomable_image, cx| {
+                            zoomable_image.set_substitute(cx, Some(difference));
+                        });
+                    });
+                })
+            });
+        }
     }
 
-    pub fn zoom_out(&mut self, _action: &ZoomOut, _window: &mut Window, cx: &mut Context<Self>) {
-        self.zoomable_image.update(cx, |zoomable_image, cx| {
-            zoomable_image.zoom_out(cx);
-        });
+    /// The shown frames of the selected and the compared image, if both are decoded.
+    fn compared_frames(&self, cx: &App) -> Option<(Arc<RenderImage>, Arc<RenderImage>)> {
+        let frame = |image: &Entity<ZoomableImage>| {
+            Some(image.read(cx).current_frame()?.render_image.clone())
+        };
+
+        frame(&self.zoomable_image).zip(frame(&self.compare_image))
     }
 
-    pub fn move_left(&mut self, _action: &MoveLeft, _window: &mut Window, cx: &mut Context<Self>) {
-        self.zoomable_image.update(cx, |zoomable_image, cx| {
-            zoomable_image.move_left(cx);
-        });
+    /// Alternate between the compared images while in flicker mode.
+    fn update_flicker(&mut self, cx: &mut Context<Self>) {
+        self.flicker_shows_compared = false;
+        self._flicker_task =
+            (self.compare && self.compare_mode == CompareMode::Flicker).then(|| {
+                let interval = Duration::from_millis(CONFIG.get().unwrap().flicker_interval);
+
+                cx.spawn(async move |this, cx| {
+                    loop {
+                        cx.background_executor().timer(interval).await;
+
+                        let updated = this.update(cx, |this, cx| {
+                            this.flicker_shows_compared = !this.flicker_shows_compared;
+                            cx.notify();
+                        });
+                        if updated.is_err() {
+                            break;
+                        }
+                    }
+                })
+            });
     }
 
-    pub fn move_right(
-        &mut self,
-        _action: &MoveRight,
-        _window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        self.zoomable_image.update(cx, |zoomable_image, cx| {
-            zoomable_image.move_right(cx);
-        });
+    fn drag_split(&mut self, event: &MouseMoveEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.dragging_split {
+            return;
+        }
+
+        if event.pressed_button != Some(MouseButton::Left) {
+            // the button was released outside of the window
+            self.dragging_split = false;
+            return;
+        }
+
+        self.split_position = (event.position.x / window.viewport_size().width).clamp(0.0, 1.0);
+
+        cx.notify();
     }
 
-    pub fn move_up(&mut self, _action: &MoveUp, _window: &mut Window, cx: &mut Context<Self>) {
+    fn update_spread_page(&mut self, cx: &mut Context<Self>) {
+        let spread_page = self.spread_page();
+        let right_to_left = self.right_to_left;
+
         self.zoomable_image.update(cx, |zoomable_image, cx| {
-            zoomable_image.move_up(cx);
+            zoomable_image.set_spread_page(cx, spread_page, right_to_left);
+        });
+    }
+
+    /// Resolve the files and directories in the background and open the images they contain,
+    /// see [Self::open_images].
+    pub fn open_paths(&mut self, paths: Vec<PathBuf>, append: bool, cx: &mut Context<Self>) {
+        cx.spawn(async move |this, cx| {
+            // listing directories and archives might take a while
+            let (paths, images) = cx
+                .background_spawn(async move {
+                    let images = ImageEntry::from_paths(resolve_image_paths(&paths));
+                    (paths, images)
+                })
+                .await;
+            if images.is_empty() {
+                return;
+            }
+
+            let _ = this.update(cx, |this, cx| {
+                if !append {
+                    this.sources.clear();
+                }
+                this.sources.extend(paths);
+
+                this.open_images(images, append, cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Append the images to the list and seek to the first new image,
+    /// or replace the current list of images if `append` is `false`.
+    ///
+    /// Nothing happens if there are no images, e.g. if only non-image files were dropped.
+    pub fn open_images(
+        &mut self,
+        mut images: Vec<ImageEntry>,
+        append: bool,
+        cx: &mut Context<Self>,
+    ) {
+        if images.is_empty() {
+            return;
+        }
+
+        if !append {
+            self.images.clear();
+            self.marked_img_indices.clear();
+        }
+
+        let prev_size = self.images.len();
+        self.images.append(&mut images);
+        self.scroll_list.reset(self.images.len());
+        self.selected_img_index =
+            self.spread_start(prev_size.min(self.images.len().saturating_sub(1)));
+
+        self.set_image(cx, self.selected_image());
+        cx.notify();
+    }
+
+    /// Open the files (and the images inside dropped directories) dropped onto the window.
+    ///
+    /// Dragging the current image out of the window isn't supported, as gpui can only start
+    /// drags within the app and not offer files to other apps. Until it can, [CopyPath] puts
+    /// the path of the current image on the clipboard instead, e.g. for pasting it into a
+    /// file manager or an upload dialog.
+    fn drop_files(&mut self, paths: &ExternalPaths, _window: &mut Window, cx: &mut Context<Self>) {
+        self.open_paths(
+            paths.paths().to_vec(),
+            !CONFIG.get().unwrap().drop_replaces,
+            cx,
+        );
+    }
+
+    /// Copy the pixels of the shown frame (or of the crop selection), e.g. to paste them
+    /// into an image editor.
+    fn copy_image(&mut self, _action: &CopyImage, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(buffer) = self.zoomable_image.read(cx).cropped_frame() else {
+            return;
+        };
+
+        let task = cx.background_spawn(async move { clipboard::image_item(&buffer) });
+        cx.spawn(async move |window, cx| match task.await {
+            Ok(item) => {
+                let _ = window.update(cx, |_, cx| cx.write_to_clipboard(item));
+            }
+            Err(err) => eprintln!("Failed to copy the image: {err}"),
+        })
+        .detach();
+    }
+
+    fn copy_path(&mut self, _action: &CopyPath, _window: &mut Window, cx: &mut Context<Self>) {
+        // pasted images don't have a path
+        if let Some(image) = self.selected_image().filter(|image| image.path().is_some()) {
+            cx.write_to_clipboard(ClipboardItem::new_string(image.to_string()));
+        }
+    }
+
+    /// Append the image of the clipboard to the list, without saving it to disk.
+    fn paste_image(&mut self, _action: &PasteImage, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(image) = cx
+            .read_from_clipboard()
+            .as_ref()
+            .and_then(clipboard::pasted_image)
+        else {
+            return;
+        };
+
+        self.open_images(vec![ImageEntry::Pasted(image)], true, cx);
+    }
+
+    /// Save the selected image to disk if it was pasted from the clipboard.
+    fn save_image(&mut self, _action: &SaveImage, _window: &mut Window, cx: &mut Context<Self>) {
+        if self
+            .zoomable_image
+            .read(cx)
+            .crop()
+            .is_some_and(|crop| crop.rect.is_some())
+        {
+            self.export_image(None, cx);
+            return;
+        }
+
+        let Some(ImageEntry::Pasted(image)) = self.selected_image() else {
+            return;
+        };
+
+        let directory = std::env::current_dir().unwrap_or_default();
+        let recv = cx.prompt_for_new_path(&directory, Some(&image.file_name()));
+
+        cx.spawn(async move |window, cx| {
+            let Some(path) = recv.await.ok().and_then(Result::ok).flatten() else {
+                return;
+            };
+
+            if let Err(err) = std::fs::write(&path, &image.data) {
+                eprintln!("Failed to save the image to {}: {err}", path.display());
+                return;
+            }
+
+            let _ = window.update(cx, |this, cx| {
+                this.replace_pasted_image(&image, path, cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Save the shown frame, cropped to the crop selection, as a new file next to the image.
+    ///
+    /// Without `options`, the frame is saved as it is. The format always matches the chosen
+    /// file extension, the extension of the format is appended if it's not a known one.
+    fn export_image(&mut self, options: Option<ExportOptions>, cx: &mut Context<Self>) {
+        let Some(buffer) = self.zoomable_image.read(cx).cropped_frame() else {
+            return;
+        };
+
+        let image = self.selected_image();
+        let info = self.image_info(cx);
+        let color_managed = info
+            .as_ref()
+            .is_some_and(|info| info.color_managed && info.color_profile.is_some());
+        let directory = image
+            .as_ref()
+            .and_then(ImageEntry::path)
+            .and_then(Path::parent)
+            .map_or_else(
+                || std::env::current_dir().unwrap_or_default(),
+                Path::to_path_buf,
+            );
+        let stem = info
+            .and_then(|info| info.file_name)
+            .and_then(|name| Some(Path::new(&name).file_stem()?.to_string_lossy().to_string()))
+            .unwrap_or_else(|| "image".to_string());
+        let file_name = match options {
+            Some(options) => format!("{stem}.{}", options.format.extension()),
+            None => format!("{stem}-cropped.png"),
+        };
+        let recv = cx.prompt_for_new_path(&directory, Some(&file_name));
+
+        cx.spawn(async move |_, cx| {
+            let Some(mut path) = recv.await.ok().and_then(Result::ok).flatten() else {
+                return;
+            };
+
+            let mut options = options.unwrap_or_default();
+            if let Some(format) = ExportFormat::from_path(&path) {
+                // the extension typed in the file dialog wins over the format chosen before
+                options.format = format;
+            } else {
+                let mut corrected = path.into_os_string();
+                corrected.push(".");
+                corrected.push(options.format.extension());
+                path = PathBuf::from(corrected);
+
+                // the file dialog only asked about overwriting the path without the extension
+                if path.exists() {
+                    eprintln!("Not overwriting {}", path.display());
+                    return;
+                }
+            }
+            let task_path = path.clone();
+            let saved = cx
+                .background_spawn(async move {
+                    export::export(&buffer, image.as_ref(), color_managed, &options, &task_path)
+                })
+                .await;
+            if let Err(err) = saved {
+                eprintln!("Failed to save the image to {}: {err}", path.display());
+            }
+        })
+        .detach();
+    }
+
+    /// Replace the pasted image with the file it was saved to.
+    fn replace_pasted_image(&mut self, image: &PastedImage, path: PathBuf, cx: &mut Context<Self>) {
+        let Some(index) = self
+            .images
+            .iter()
+            .position(|entry| matches!(entry, ImageEntry::Pasted(pasted) if pasted == image))
+        else {
+            return;
+        };
+
+        self.images[index] = ImageEntry::File(path);
+        if index == self.selected_img_index {
+            self.set_image(cx, self.selected_image());
+        }
+
+        cx.notify();
+    }
+
+    #[allow(clippy::unused_self)]
+    fn open_help(&mut self, _action: &Help, _window: &mut Window, cx: &mut Context<Self>) {
+        let window_bounds =
+            WindowBounds::Windowed(Bounds::centered(None, size(px(300.0), px(300.0)), cx));
+
+        let _ = cx.open_window(
+            WindowOptions {
+                window_bounds: Some(window_bounds),
+                kind: WindowKind::Dialog,
+                ..Default::default()
+            },
+            |window, cx| cx.new(|cx| HelpWindow::new(window, cx)),
+        );
+    }
+
+    #[allow(clippy::unused_self)]
+    fn toggle_fullscreen(
+        &mut self,
+        _action: &ToggleFullscreen,
+        window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        window.toggle_fullscreen();
+    }
+
+    #[allow(clippy::unused_self)]
+    fn open_files(&mut self, _action: &OpenFiles, _window: &mut Window, cx: &mut Context<Self>) {
+        let recv = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: true,
+            prompt: None,
+        });
+
+        cx.spawn(async |window, cx| {
+            let selection_result = recv
+                .await
+                .ok()
+                .and_then(std::result::Result::ok)
+                .and_then(|res| res);
+
+            if let Some(images) = selection_result {
+                let _ = window.update(cx, |this, cx| {
+                    this.open_paths(images, true, cx);
+                });
+            }
+        })
+        .detach();
+    }
+
+    pub fn zoom_in(&mut self, _action: &ZoomIn, _window: &mut Window, cx: &mut Context<Self>) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.zoom_in(cx);
+        });
+    }
+
+    pub fn zoom_out(&mut self, _action: &ZoomOut, _window: &mut Window, cx: &mut Context<Self>) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.zoom_out(cx);
+        });
+    }
+
+    pub fn move_left(&mut self, _action: &MoveLeft, _window: &mut Window, cx: &mut Context<Self>) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.move_left(cx);
+        });
+    }
+
+    pub fn move_right(
+        &mut self,
+        _action: &MoveRight,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.move_right(cx);
+        });
+    }
+
+    pub fn move_up(&mut self, _action: &MoveUp, _window: &mut Window, cx: &mut Context<Self>) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.move_up(cx);
         });
     }
 
@@ -252,4 +1440,562 @@ impl AppWindow {
 
         cx.notify();
     }
+
+    pub fn toggle_playback(
+        &mut self,
+        _action: &TogglePlayback,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.toggle_playback(cx);
+        });
+    }
+
+    pub fn next_frame(
+        &mut self,
+        _action: &NextFrame,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.step_frame(cx, 1);
+        });
+    }
+
+    pub fn prev_frame(
+        &mut self,
+        _action: &PreviousFrame,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.step_frame(cx, -1);
+        });
+    }
+
+    pub fn increase_playback_speed(
+        &mut self,
+        _action: &IncreasePlaybackSpeed,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.change_playback_speed(cx, 2.0);
+        });
+    }
+
+    pub fn decrease_playback_speed(
+        &mut self,
+        _action: &DecreasePlaybackSpeed,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.change_playback_speed(cx, 0.5);
+        });
+    }
+
+    pub fn toggle_animation_loop(
+        &mut self,
+        _action: &ToggleAnimationLoop,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.toggle_looping(cx);
+        });
+    }
+
+    pub fn next_page(&mut self, _action: &NextPage, _window: &mut Window, cx: &mut Context<Self>) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.change_page(cx, 1);
+        });
+    }
+
+    pub fn prev_page(
+        &mut self,
+        _action: &PreviousPage,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.change_page(cx, -1);
+        });
+    }
+
+    pub fn toggle_spread(
+        &mut self,
+        _action: &ToggleSpread,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.spread = !self.spread;
+
+        let spread_start = self.spread_start(self.selected_img_index);
+        if spread_start == self.selected_img_index {
+            self.update_spread_page(cx);
+        } else {
+            self.selected_img_index = spread_start;
+            self.set_image(cx, self.selected_image());
+        }
+
+        cx.notify();
+    }
+
+    pub fn toggle_reading_direction(
+        &mut self,
+        _action: &ToggleReadingDirection,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.right_to_left = !self.right_to_left;
+        self.update_spread_page(cx);
+    }
+
+    pub fn toggle_continuous_scroll(
+        &mut self,
+        _action: &ToggleContinuousScroll,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.continuous_scroll = !self.continuous_scroll;
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.set_hidden(self.continuous_scroll, cx);
+        });
+        if !self.continuous_scroll {
+            self.evict_previews(0..0, cx);
+        }
+
+        // spreads are disabled while scrolling, so the selected image might not start a spread
+        let spread_start = self.spread_start(self.selected_img_index);
+        if spread_start == self.selected_img_index {
+            self.update_spread_page(cx);
+            self.scroll_to_selected_image();
+        } else {
+            self.selected_img_index = spread_start;
+            self.set_image(cx, self.selected_image());
+        }
+
+        cx.notify();
+    }
+
+    pub fn toggle_color_management(
+        &mut self,
+        _action: &ToggleColorManagement,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.toggle_color_management(cx);
+        });
+        self.compare_image.update(cx, |compare_image, cx| {
+            compare_image.toggle_color_management(cx);
+        });
+        // the previews are loaded again with the new setting, which is part of their key
+        self.evict_previews(0..0, cx);
+    }
+
+    pub fn toggle_histogram(
+        &mut self,
+        _action: &ToggleHistogram,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_histogram = !self.show_histogram;
+
+        cx.notify();
+    }
+
+    pub fn toggle_histogram_scale(
+        &mut self,
+        _action: &ToggleHistogramScale,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.histogram_settings.logarithmic = !self.histogram_settings.logarithmic;
+
+        cx.notify();
+    }
+
+    pub fn toggle_histogram_channel(
+        &mut self,
+        action: &ToggleHistogramChannel,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.histogram_settings.toggle(action.channel);
+
+        cx.notify();
+    }
+
+    pub fn toggle_pixel_inspector(
+        &mut self,
+        _action: &TogglePixelInspector,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.toggle_pixel_inspector(cx);
+        });
+    }
+
+    /// Copy the color of the pixel under the cursor as hex string, e.g. "#1e1e2eff".
+    pub fn copy_color(
+        &mut self,
+        _action: &CopyColor,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(pixel) = self.zoomable_image.read(cx).inspected_pixel() {
+            cx.write_to_clipboard(ClipboardItem::new_string(pixel.color.to_string()));
+        }
+    }
+
+    pub fn cycle_backdrop(
+        &mut self,
+        _action: &CycleBackdrop,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.cycle_backdrop(cx);
+        });
+        self.compare_image.update(cx, |compare_image, cx| {
+            compare_image.cycle_backdrop(cx);
+        });
+
+        // the images in continuous scroll mode use the same backdrop
+        cx.notify();
+    }
+
+    pub fn cycle_sampling(
+        &mut self,
+        _action: &CycleSampling,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.cycle_sampling(cx);
+        });
+        self.compare_image.update(cx, |compare_image, cx| {
+            compare_image.cycle_sampling(cx);
+        });
+    }
+
+    pub fn toggle_pixel_grid(
+        &mut self,
+        _action: &TogglePixelGrid,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.toggle_pixel_grid(cx);
+        });
+        self.compare_image.update(cx, |compare_image, cx| {
+            compare_image.toggle_pixel_grid(cx);
+        });
+    }
+
+    /// Compare the selected image with the first other marked image or the next image.
+    pub fn toggle_compare(
+        &mut self,
+        _action: &ToggleCompare,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.compare = !self.compare;
+
+        // spreads are disabled while comparing, so the selected image might not start a spread
+        let spread_start = self.spread_start(self.selected_img_index);
+        if spread_start == self.selected_img_index {
+            self.update_spread_page(cx);
+            self.update_compare_image(cx);
+        } else {
+            self.selected_img_index = spread_start;
+            self.set_image(cx, self.selected_image());
+        }
+        self.update_flicker(cx);
+        self.update_difference(cx);
+
+        cx.notify();
+    }
+
+    /// Switch to the next way of comparing the images, starting to compare if necessary.
+    pub fn cycle_compare_mode(
+        &mut self,
+        _action: &CycleCompareMode,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.compare_mode = self.compare_mode.next();
+
+        if self.compare {
+            self.update_flicker(cx);
+            self.update_difference(cx);
+            cx.notify();
+        } else {
+            self.toggle_compare(&ToggleCompare, window, cx);
+        }
+    }
+
+    pub fn increase_blend_opacity(
+        &mut self,
+        _action: &IncreaseBlendOpacity,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.blend_opacity = (self.blend_opacity + 0.1).min(1.0);
+
+        cx.notify();
+    }
+
+    pub fn decrease_blend_opacity(
+        &mut self,
+        _action: &DecreaseBlendOpacity,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.blend_opacity = (self.blend_opacity - 0.1).max(0.0);
+
+        cx.notify();
+    }
+
+    pub fn toggle_crop(
+        &mut self,
+        _action: &ToggleCrop,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.toggle_crop(cx);
+        });
+    }
+
+    pub fn cycle_crop_aspect_ratio(
+        &mut self,
+        _action: &CycleCropAspectRatio,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.cycle_crop_aspect_ratio(cx);
+        });
+    }
+
+    pub fn toggle_crop_preview(
+        &mut self,
+        _action: &ToggleCropPreview,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.toggle_crop_preview(cx);
+        });
+    }
+
+    /// Type the values of the crop rectangle, see [crate::crop::Crop::type_key].
+    fn type_crop_key(
+        &mut self,
+        event: &KeyDownEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // e.g. ctrl-1 might be the key binding of a custom command
+        if event.keystroke.modifiers.modified() {
+            return;
+        }
+
+        let typed = self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.type_crop_key(cx, &event.keystroke.key)
+        });
+
+        if typed {
+            cx.stop_propagation();
+        }
+    }
+
+    /// Open the dialog for saving the shown frame in another format or size, or close it.
+    pub fn save_as(&mut self, _action: &SaveAs, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.save_as_dialog.take().is_some() {
+            cx.notify();
+            return;
+        }
+
+        let Some(image_size) = self.zoomable_image.read(cx).cropped_size() else {
+            return;
+        };
+
+        let options = self.export_options;
+        let dialog = cx.new(|_| SaveAsDialog::new(options, image_size));
+        cx.subscribe(&dialog, |this, _, event, cx| {
+            this.save_as_dialog = None;
+            if let SaveAsEvent::Save(options) = event {
+                this.export_options = *options;
+                this.export_image(Some(*options), cx);
+            }
+
+            cx.notify();
+        })
+        .detach();
+        self.save_as_dialog = Some(dialog);
+
+        cx.notify();
+    }
+
+    /// Change an adjustment of the shown image, which the compared image shares.
+    fn step_adjustment(&mut self, adjustment: Adjustment, sign: f32, cx: &mut Context<Self>) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.adjust(cx, |adjustments| adjustments.step(adjustment, sign));
+        });
+
+        cx.notify();
+    }
+
+    pub fn increase_exposure(
+        &mut self,
+        _action: &IncreaseExposure,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step_adjustment(Adjustment::Exposure, 1.0, cx);
+    }
+
+    pub fn decrease_exposure(
+        &mut self,
+        _action: &DecreaseExposure,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step_adjustment(Adjustment::Exposure, -1.0, cx);
+    }
+
+    pub fn increase_contrast(
+        &mut self,
+        _action: &IncreaseContrast,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step_adjustment(Adjustment::Contrast, 1.0, cx);
+    }
+
+    pub fn decrease_contrast(
+        &mut self,
+        _action: &DecreaseContrast,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step_adjustment(Adjustment::Contrast, -1.0, cx);
+    }
+
+    pub fn increase_gamma(
+        &mut self,
+        _action: &IncreaseGamma,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step_adjustment(Adjustment::Gamma, 1.0, cx);
+    }
+
+    pub fn decrease_gamma(
+        &mut self,
+        _action: &DecreaseGamma,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step_adjustment(Adjustment::Gamma, -1.0, cx);
+    }
+
+    pub fn increase_saturation(
+        &mut self,
+        _action: &IncreaseSaturation,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step_adjustment(Adjustment::Saturation, 1.0, cx);
+    }
+
+    pub fn decrease_saturation(
+        &mut self,
+        _action: &DecreaseSaturation,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.step_adjustment(Adjustment::Saturation, -1.0, cx);
+    }
+
+    pub fn reset_adjustments(
+        &mut self,
+        _action: &ResetAdjustments,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.adjust(cx, |adjustments| *adjustments = Adjustments::default());
+        });
+
+        cx.notify();
+    }
+
+    fn toggle_channel(&mut self, channel: ChannelView, cx: &mut Context<Self>) {
+        self.zoomable_image.update(cx, |zoomable_image, cx| {
+            zoomable_image.toggle_channel(cx, channel);
+        });
+
+        cx.notify();
+    }
+
+    pub fn show_red_channel(
+        &mut self,
+        _action: &ShowRedChannel,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_channel(ChannelView::Red, cx);
+    }
+
+    pub fn show_green_channel(
+        &mut self,
+        _action: &ShowGreenChannel,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_channel(ChannelView::Green, cx);
+    }
+
+    pub fn show_blue_channel(
+        &mut self,
+        _action: &ShowBlueChannel,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_channel(ChannelView::Blue, cx);
+    }
+
+    pub fn show_alpha_channel(
+        &mut self,
+        _action: &ShowAlphaChannel,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_channel(ChannelView::Alpha, cx);
+    }
+
+    pub fn show_alpha_mask(
+        &mut self,
+        _action: &ShowAlphaMask,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_channel(ChannelView::AlphaMask, cx);
+    }
+
+    pub fn show_premultiplied_alpha(
+        &mut self,
+        _action: &ShowPremultipliedAlpha,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.toggle_channel(ChannelView::Premultiplied, cx);
+    }
 }